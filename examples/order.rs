@@ -4,8 +4,7 @@
 use apca::api::v2::order;
 use apca::ApiInfo;
 use apca::Client;
-
-use num_decimal::Num;
+use apca::Price;
 
 #[tokio::main]
 async fn main() {
@@ -23,7 +22,7 @@ async fn main() {
   // 100.
   let request = order::OrderReqInit {
     type_: order::Type::Limit,
-    limit_price: Some(Num::from(100)),
+    limit_price: Some(Price::from(100)),
     ..Default::default()
   }
   // We want to go long on AAPL, buying a single share.
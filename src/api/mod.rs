@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 /// Definitions for the second version of the Alpaca API.
+#[cfg(feature = "trading")]
 pub mod v2;
 
 /// The API base URL used for paper trading.
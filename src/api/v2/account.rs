@@ -13,6 +13,7 @@ use serde::Serialize;
 
 use uuid::Uuid;
 
+use crate::Notional;
 use crate::Str;
 
 
@@ -77,7 +78,7 @@ pub struct Account {
   pub currency: String,
   /// Cash balance.
   #[serde(rename = "cash")]
-  pub cash: Num,
+  pub cash: Notional,
   /// Whether or not the account has been flagged as a pattern day
   /// trader.
   #[serde(rename = "pattern_day_trader")]
@@ -103,17 +104,17 @@ pub struct Account {
   /// Real-time mark-to-market value of all long positions held in the
   /// account.
   #[serde(rename = "long_market_value")]
-  pub market_value_long: Num,
+  pub market_value_long: Notional,
   /// Real-time mark-to-market value of all short positions held in the
   /// account.
   #[serde(rename = "short_market_value")]
-  pub market_value_short: Num,
+  pub market_value_short: Notional,
   /// The sum of `cash`, `market_value_long`, and `market_value_short`.
   #[serde(rename = "equity")]
-  pub equity: Num,
+  pub equity: Notional,
   /// Equity as of previous trading day at 16:00:00 ET.
   #[serde(rename = "last_equity")]
-  pub last_equity: Num,
+  pub last_equity: Notional,
   /// Buying power multiplier that represents account margin
   /// classification. Valid values are:
   /// - 1: the standard limited margin account with 1x buying power
@@ -130,13 +131,13 @@ pub struct Account {
   /// - 2: max(equity – initial_margin, 0) * 2
   /// - 4: (last_equity - (last) maintenance_margin) * 4
   #[serde(rename = "buying_power")]
-  pub buying_power: Num,
+  pub buying_power: Notional,
   /// Initial margin requirement (this value is continuously updated).
   #[serde(rename = "initial_margin")]
-  pub initial_margin: Num,
+  pub initial_margin: Notional,
   /// Maintenance margin requirement (this value is continuously updated).
   #[serde(rename = "maintenance_margin")]
-  pub maintenance_margin: Num,
+  pub maintenance_margin: Notional,
   /// The current number of day trades that have been made in the last
   /// five trading days (including today).
   #[serde(rename = "daytrade_count")]
@@ -213,17 +214,17 @@ mod tests {
     assert_eq!(acc.id, id);
     assert_eq!(acc.status, Status::Active);
     assert_eq!(acc.currency, "USD");
-    assert_eq!(acc.buying_power, Num::from(0));
+    assert_eq!(acc.buying_power, Notional::from(0));
     assert!(!acc.trading_blocked);
     assert_eq!(
       acc.created_at,
       DateTime::parse_from_rfc3339("2018-10-01T13:35:25Z").unwrap()
     );
-    assert_eq!(acc.market_value_long, Num::from(7000));
-    assert_eq!(acc.market_value_short, Num::from(-3000));
-    assert_eq!(acc.equity, Num::from(5000));
-    assert_eq!(acc.last_equity, Num::from(5000));
-    assert_eq!(acc.maintenance_margin, Num::from(3000));
+    assert_eq!(acc.market_value_long, Notional::from(7000));
+    assert_eq!(acc.market_value_short, Notional::from(-3000));
+    assert_eq!(acc.equity, Notional::from(5000));
+    assert_eq!(acc.last_equity, Notional::from(5000));
+    assert_eq!(acc.maintenance_margin, Notional::from(3000));
     assert_eq!(acc.daytrade_count, 0);
   }
 
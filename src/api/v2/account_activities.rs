@@ -17,9 +17,14 @@ use crate::api::v2::de::TaggedContentVisitor;
 use crate::api::v2::order;
 use crate::util::abs_num_from_str;
 use crate::util::enum_slice_to_str;
+use crate::Paginated;
 use crate::Str;
 
 
+/// The maximum (and default) number of activities reported per page.
+const MAX_PAGE_SIZE: usize = 100;
+
+
 /// Deserialize a `DateTime<Utc>` from a simple date.
 fn datetime_from_date_str<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
@@ -419,6 +424,28 @@ Endpoint! {
   }
 }
 
+impl Paginated for Get {
+  type Item = Activity;
+
+  fn into_page(
+    input: &Self::Input,
+    output: Self::Output,
+  ) -> (Vec<Self::Item>, Option<Self::Input>) {
+    // The endpoint does not report whether more pages are available,
+    // so we only ask for another one if the current page is full. Note
+    // that larger page sizes than the maximum are silently capped.
+    let page_size = input.page_size.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let next = match output.last() {
+      Some(last) if output.len() >= page_size => Some(ActivityReq {
+        page_token: Some(last.id().to_string()),
+        ..input.clone()
+      }),
+      _ => None,
+    };
+    (output, next)
+  }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -522,6 +549,47 @@ mod tests {
     assert_eq!(non_trade.per_share_amount, Some(Num::new(108783, 1000000)));
   }
 
+  /// Check that we only request another page of activities if the
+  /// current one is full.
+  #[test]
+  fn activity_pages() {
+    let activity = |id: &str| {
+      let json = format!(
+        r#"{{"activity_type":"DIV","id":"{}","date":"2019-08-01","net_amount":"1.02"}}"#,
+        id
+      );
+      from_json::<Activity>(&json).unwrap()
+    };
+
+    let request = ActivityReq {
+      page_size: Some(2),
+      ..Default::default()
+    };
+    let (page, next) = Get::into_page(&request, vec![activity("1"), activity("2")]);
+    assert_eq!(page.len(), 2);
+    assert_eq!(next.unwrap().page_token, Some("2".to_string()));
+
+    let (page, next) = Get::into_page(&request, vec![activity("3")]);
+    assert_eq!(page.len(), 1);
+    assert_eq!(next, None);
+
+    let (page, next) = Get::into_page(&request, Vec::new());
+    assert!(page.is_empty());
+    assert_eq!(next, None);
+
+    // A page capped at the maximum size is considered full.
+    let request = ActivityReq {
+      page_size: Some(2 * MAX_PAGE_SIZE),
+      ..Default::default()
+    };
+    let output = (1..=MAX_PAGE_SIZE)
+      .map(|id| activity(&id.to_string()))
+      .collect();
+    let (page, next) = Get::into_page(&request, output);
+    assert_eq!(page.len(), MAX_PAGE_SIZE);
+    assert_eq!(next.unwrap().page_token, Some(MAX_PAGE_SIZE.to_string()));
+  }
+
   #[test(tokio::test)]
   async fn retrieve_some_activities() {
     let api_info = ApiInfo::from_env().unwrap();
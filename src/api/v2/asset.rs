@@ -8,6 +8,8 @@ use std::fmt::Result as FmtResult;
 use std::ops::Deref;
 use std::str::FromStr;
 
+use num_decimal::Num;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
@@ -15,6 +17,8 @@ use serde::Serializer;
 use uuid::Error as UuidError;
 use uuid::Uuid;
 
+use crate::Price;
+use crate::Quantity;
 use crate::Str;
 
 
@@ -349,6 +353,65 @@ pub struct Asset {
   /// Whether the asset is fractionable or not.
   #[serde(rename = "fractionable")]
   pub fractionable: bool,
+  /// The minimum quantity of the asset an order can be submitted for.
+  ///
+  /// This property is currently only reported for crypto currencies.
+  #[serde(rename = "min_order_size")]
+  pub min_order_size: Option<Quantity>,
+  /// The increment in which quantities of the asset can be traded.
+  ///
+  /// This property is currently only reported for crypto currencies.
+  #[serde(rename = "min_trade_increment")]
+  pub min_trade_increment: Option<Quantity>,
+  /// The increment in which prices of the asset are quoted.
+  ///
+  /// This property is currently only reported for crypto currencies.
+  #[serde(rename = "price_increment")]
+  pub price_increment: Option<Price>,
+}
+
+impl Asset {
+  /// Round the provided (non-negative) quantity down to a multiple of
+  /// the asset's minimum trade increment, if any.
+  ///
+  /// `None` is returned if the rounded quantity is zero or below the
+  /// asset's minimum order size, i.e., if no order could be submitted
+  /// for it.
+  pub fn round_quantity(&self, quantity: &Quantity) -> Option<Quantity> {
+    let quantity = match &self.min_trade_increment {
+      Some(increment) if increment.is_positive() => {
+        Quantity(multiple_of(quantity, increment, Num::trunc))
+      },
+      _ => quantity.clone(),
+    };
+
+    if quantity.is_zero() {
+      return None
+    }
+
+    match &self.min_order_size {
+      Some(min_order_size) if &quantity < min_order_size => None,
+      _ => Some(quantity),
+    }
+  }
+
+  /// Round the provided price to the nearest multiple of the asset's
+  /// price increment, if any.
+  pub fn round_price(&self, price: &Price) -> Price {
+    match &self.price_increment {
+      Some(increment) if increment.is_positive() => {
+        Price(multiple_of(price, increment, Num::round))
+      },
+      _ => price.clone(),
+    }
+  }
+}
+
+
+/// Round `num` to a multiple of `increment`, using the provided
+/// function for rounding the number of increments.
+fn multiple_of(num: &Num, increment: &Num, round: fn(&Num) -> Num) -> Num {
+  round(&(num / increment)) * increment
 }
 
 
@@ -477,6 +540,73 @@ mod tests {
     assert!(asset.marginable);
     assert!(asset.shortable);
     assert!(asset.easy_to_borrow);
+    assert_eq!(asset.min_order_size, None);
+  }
+
+  /// Check that we can parse a crypto asset object including its
+  /// precision metadata.
+  #[test]
+  fn parse_crypto_asset() {
+    let response = r#"{
+  "id": "64bbff51-59d6-4b3c-9351-13ad85e3c752",
+  "class": "crypto",
+  "exchange": "FTXU",
+  "symbol": "BTCUSD",
+  "name": "Bitcoin",
+  "status": "active",
+  "tradable": true,
+  "marginable": false,
+  "shortable": false,
+  "easy_to_borrow": false,
+  "fractionable": true,
+  "min_order_size": "0.0001",
+  "min_trade_increment": "0.0001",
+  "price_increment": "1"
+}"#;
+
+    let asset = from_json::<Asset>(response).unwrap();
+    assert_eq!(asset.class, Class::Crypto);
+    assert_eq!(asset.min_order_size, Some(Quantity(Num::new(1, 10000))));
+    assert_eq!(
+      asset.min_trade_increment,
+      Some(Quantity(Num::new(1, 10000)))
+    );
+    assert_eq!(asset.price_increment, Some(Price::from(1)));
+  }
+
+  /// Check that quantities and prices are rounded to valid increments.
+  #[test]
+  fn round_to_increments() {
+    let response = r#"{
+  "id": "35f33a69-f5d6-4dc9-b158-4485e5e92e4b",
+  "class": "crypto",
+  "exchange": "FTXU",
+  "symbol": "ETHUSD",
+  "status": "active",
+  "tradable": true,
+  "marginable": false,
+  "shortable": false,
+  "easy_to_borrow": false,
+  "fractionable": true,
+  "min_order_size": "0.001",
+  "min_trade_increment": "0.0005",
+  "price_increment": "0.1"
+}"#;
+    let asset = from_json::<Asset>(response).unwrap();
+
+    let quantity = Quantity(Num::new(12349, 10000));
+    assert_eq!(
+      asset.round_quantity(&quantity),
+      Some(Quantity(Num::new(12345, 10000)))
+    );
+    let quantity = Quantity(Num::new(9, 10000));
+    assert_eq!(asset.round_quantity(&quantity), None);
+    assert_eq!(asset.round_quantity(&Quantity::from(0)), None);
+
+    let price = Price(Num::new(300016, 100));
+    assert_eq!(asset.round_price(&price), Price(Num::new(30002, 10)));
+    let price = Price(Num::new(300014, 100));
+    assert_eq!(asset.round_price(&price), Price(Num::new(30001, 10)));
   }
 
   /// Verify that we can parse an asset object with an unknown exchange.
@@ -0,0 +1,506 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::SendError;
+use futures::channel::mpsc::Sender;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::future::ready;
+use futures::future::select;
+use futures::future::Either;
+use futures::pin_mut;
+use futures::stream::once;
+use futures::stream::select as select_stream;
+use futures::FutureExt as _;
+use futures::SinkExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use serde_json::Error as JsonError;
+
+use tokio::time::sleep_until;
+use tokio::time::Instant;
+
+use tracing::debug;
+use tracing::warn;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+
+use crate::api::v2::updates::OrderUpdate;
+use crate::Error;
+
+
+/// The default time after which an unacknowledged delivery is
+/// attempted again.
+const DEFAULT_REDELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+
+/// An ID identifying a single delivery of an order update, used for
+/// acknowledging it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DeliveryId(pub u64);
+
+
+/// An order update as republished by a [`Bridge`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delivery {
+  /// The ID of the delivery, to be passed to [`Acker::ack`] once the
+  /// update has been processed for good.
+  pub id: DeliveryId,
+  /// The delivery attempt, starting at one. Values greater than one
+  /// indicate that the update may already have been seen before.
+  pub attempt: usize,
+  /// The order update being delivered.
+  pub update: OrderUpdate,
+}
+
+
+/// A trait representing a destination that order updates can be
+/// republished to.
+#[async_trait]
+pub trait Publisher {
+  /// The error that publishing may result in.
+  type Error: Debug;
+
+  /// Publish a single delivery.
+  ///
+  /// A successful publish does not constitute an acknowledgment of the
+  /// delivery. Unless acknowledged, it will be delivered again.
+  async fn publish(&mut self, delivery: Delivery) -> Result<(), Self::Error>;
+}
+
+#[async_trait]
+impl Publisher for Sender<Delivery> {
+  type Error = SendError;
+
+  async fn publish(&mut self, delivery: Delivery) -> Result<(), Self::Error> {
+    self.send(delivery).await
+  }
+}
+
+#[async_trait]
+impl Publisher for UnboundedSender<Delivery> {
+  type Error = SendError;
+
+  async fn publish(&mut self, delivery: Delivery) -> Result<(), Self::Error> {
+    self
+      .unbounded_send(delivery)
+      .map_err(|err| err.into_send_error())
+  }
+}
+
+
+/// A [`Publisher`] invoking an asynchronous callback for each
+/// delivery.
+#[derive(Clone, Copy, Debug)]
+pub struct Callback<F>(pub F);
+
+#[async_trait]
+impl<F, Fut, E> Publisher for Callback<F>
+where
+  F: FnMut(Delivery) -> Fut + Send,
+  Fut: Future<Output = Result<(), E>> + Send,
+  E: Debug,
+{
+  type Error = E;
+
+  async fn publish(&mut self, delivery: Delivery) -> Result<(), Self::Error> {
+    (self.0)(delivery).await
+  }
+}
+
+
+/// A handle for acknowledging deliveries made by a [`Bridge`].
+#[derive(Clone, Debug)]
+pub struct Acker(UnboundedSender<DeliveryId>);
+
+impl Acker {
+  /// Acknowledge the delivery with the given ID, preventing any
+  /// further delivery attempts for it.
+  ///
+  /// Acknowledging an unknown or already acknowledged delivery has no
+  /// effect.
+  #[inline]
+  pub fn ack(&self, id: DeliveryId) {
+    // If the bridge is gone there is nobody left to care about the
+    // acknowledgment.
+    let _ = self.0.unbounded_send(id);
+  }
+}
+
+
+/// A delivery that has not been acknowledged yet.
+#[derive(Debug)]
+struct Pending {
+  /// The update being delivered.
+  update: OrderUpdate,
+  /// The number of delivery attempts made so far.
+  attempt: usize,
+  /// The time at which to attempt delivery again.
+  deadline: Instant,
+}
+
+
+/// The delivery state of a `Bridge`.
+#[derive(Debug)]
+struct State<P> {
+  /// The publisher to deliver updates to.
+  publisher: P,
+  /// The time after which to attempt an unacknowledged delivery again.
+  timeout: Duration,
+  /// The ID to use for the next new delivery.
+  next_id: u64,
+  /// All unacknowledged deliveries.
+  pending: BTreeMap<DeliveryId, Pending>,
+}
+
+impl<P> State<P>
+where
+  P: Publisher,
+{
+  /// Attempt delivery of a pending update.
+  async fn deliver(&mut self, id: DeliveryId) {
+    let pending = match self.pending.get_mut(&id) {
+      Some(pending) => pending,
+      None => return,
+    };
+
+    pending.attempt += 1;
+    pending.deadline = Instant::now() + self.timeout;
+
+    let delivery = Delivery {
+      id,
+      attempt: pending.attempt,
+      update: pending.update.clone(),
+    };
+
+    debug!(
+      id = id.0,
+      attempt = delivery.attempt,
+      "publishing order update"
+    );
+    if let Err(err) = self.publisher.publish(delivery).await {
+      // The delivery stays pending and will be attempted again once
+      // its deadline has been reached.
+      warn!(
+        id = id.0,
+        error = debug(&err),
+        "failed to publish order update"
+      );
+    }
+  }
+
+  /// Deliver a newly received update.
+  async fn deliver_new(&mut self, update: OrderUpdate) {
+    let id = DeliveryId(self.next_id);
+    self.next_id += 1;
+
+    let pending = Pending {
+      update,
+      attempt: 0,
+      deadline: Instant::now(),
+    };
+    let _prev = self.pending.insert(id, pending);
+    self.deliver(id).await
+  }
+
+  /// Deliver all pending updates whose deadline has been reached.
+  async fn redeliver(&mut self, now: Instant) {
+    let expired = self
+      .pending
+      .iter()
+      .filter(|(_, pending)| pending.deadline <= now)
+      .map(|(id, _)| *id)
+      .collect::<Vec<_>>();
+
+    for id in expired {
+      self.deliver(id).await
+    }
+  }
+
+  /// Retrieve the earliest deadline of all pending deliveries.
+  fn next_deadline(&self) -> Option<Instant> {
+    self.pending.values().map(|pending| pending.deadline).min()
+  }
+}
+
+
+/// An event processed by a `Bridge`.
+#[allow(clippy::large_enum_variant)]
+enum Event {
+  /// An item received from the order update stream.
+  Update(Result<Result<OrderUpdate, JsonError>, WebSocketError>),
+  /// The order update stream ended.
+  End,
+  /// A delivery got acknowledged.
+  Ack(DeliveryId),
+}
+
+
+/// A component republishing order updates received through the
+/// [`OrderUpdates`][crate::api::v2::updates::OrderUpdates] stream to a
+/// [`Publisher`], such as an asynchronous callback or a channel.
+///
+/// Delivery happens at least once: each delivery has to be
+/// acknowledged using an [`Acker`] and unacknowledged ones are
+/// delivered again after a timeout. Pending deliveries are kept in
+/// memory only. They survive [`Bridge::run`] returning (e.g., because
+/// the stream ended and has to be reconnected), but are lost when the
+/// `Bridge` is dropped, unless retrieved via
+/// [`Bridge::into_pending`].
+#[derive(Debug)]
+pub struct Bridge<P> {
+  /// The delivery state.
+  state: State<P>,
+  /// The sending side of the acknowledgment channel.
+  ack_send: UnboundedSender<DeliveryId>,
+  /// The receiving side of the acknowledgment channel.
+  ack_recv: UnboundedReceiver<DeliveryId>,
+}
+
+impl<P> Bridge<P>
+where
+  P: Publisher,
+{
+  /// Create a new `Bridge` republishing order updates to the provided
+  /// publisher.
+  pub fn new(publisher: P) -> Self {
+    let (ack_send, ack_recv) = unbounded();
+
+    Self {
+      state: State {
+        publisher,
+        timeout: DEFAULT_REDELIVERY_TIMEOUT,
+        next_id: 1,
+        pending: BTreeMap::new(),
+      },
+      ack_send,
+      ack_recv,
+    }
+  }
+
+  /// Set the time after which an unacknowledged delivery is attempted
+  /// again. It defaults to 30 seconds.
+  #[inline]
+  pub fn with_redelivery_timeout(mut self, timeout: Duration) -> Self {
+    self.state.timeout = timeout;
+    self
+  }
+
+  /// Retrieve an [`Acker`] for acknowledging deliveries.
+  #[inline]
+  pub fn acker(&self) -> Acker {
+    Acker(self.ack_send.clone())
+  }
+
+  /// Process all acknowledgments received so far.
+  fn process_acks(&mut self) {
+    while let Some(Some(id)) = self.ack_recv.next().now_or_never() {
+      let _pending = self.state.pending.remove(&id);
+    }
+  }
+
+  /// Retrieve the number of unacknowledged deliveries.
+  pub fn pending(&mut self) -> usize {
+    self.process_acks();
+    self.state.pending.len()
+  }
+
+  /// Republish the updates of the provided order update stream.
+  ///
+  /// This method returns once the stream ended or an error was
+  /// encountered. Any deliveries not acknowledged by then are attempted
+  /// again on a subsequent invocation.
+  pub async fn run<S>(&mut self, stream: S) -> Result<(), Error>
+  where
+    S: Stream<Item = Result<Result<OrderUpdate, JsonError>, WebSocketError>>,
+  {
+    self.process_acks();
+    self.state.redeliver(Instant::now()).await;
+
+    let updates = stream.map(Event::Update).chain(once(ready(Event::End)));
+    let acks = (&mut self.ack_recv).map(Event::Ack);
+    let events = select_stream(updates, acks);
+    pin_mut!(events);
+
+    loop {
+      let event = match self.state.next_deadline() {
+        Some(deadline) => {
+          let timeout = sleep_until(deadline);
+          pin_mut!(timeout);
+
+          match select(events.next(), timeout).await {
+            Either::Left((event, _)) => event,
+            Either::Right(((), _)) => {
+              self.state.redeliver(Instant::now()).await;
+              continue
+            },
+          }
+        },
+        None => events.next().await,
+      };
+
+      match event {
+        Some(Event::Update(Ok(Ok(update)))) => self.state.deliver_new(update).await,
+        Some(Event::Update(Ok(Err(err)))) => return Err(Error::Json(err)),
+        Some(Event::Update(Err(err))) => return Err(Error::WebSocket(err)),
+        Some(Event::Ack(id)) => {
+          let _pending = self.state.pending.remove(&id);
+        },
+        Some(Event::End) | None => return Ok(()),
+      }
+    }
+  }
+
+  /// Destroy the `Bridge`, returning all unacknowledged deliveries.
+  pub fn into_pending(mut self) -> Vec<Delivery> {
+    self.process_acks();
+    self
+      .state
+      .pending
+      .into_iter()
+      .map(|(id, pending)| Delivery {
+        id,
+        attempt: pending.attempt,
+        update: pending.update,
+      })
+      .collect()
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use futures::channel::mpsc::channel;
+  use futures::stream::iter;
+  use futures::stream::pending;
+
+  use serde_json::from_str as json_from_str;
+
+  use test_log::test;
+
+  use crate::api::v2::updates::OrderStatus;
+
+
+  /// Create an order update with the given event.
+  fn update(event: &str) -> OrderUpdate {
+    let json = format!(
+      r#"{{
+  "event":"{}","order":{{
+    "asset_class":"us_equity","asset_id":"11111111-2222-3333-4444-555555555555",
+    "canceled_at":null,"client_order_id":"11111111-2222-3333-4444-555555555555",
+    "created_at":"2021-12-09T19:48:46.176628398Z","expired_at":null,
+    "extended_hours":false,"failed_at":null,"filled_at":null,
+    "filled_avg_price":null,"filled_qty":"0","hwm":null,
+    "id":"11111111-2222-3333-4444-555555555555","legs":null,"limit_price":"1",
+    "notional":null,"order_class":"simple","order_type":"limit","qty":"1",
+    "replaced_at":null,"replaced_by":null,"replaces":null,"side":"buy",
+    "status":"new","stop_price":null,"submitted_at":"2021-12-09T19:48:46.175261379Z",
+    "symbol":"AAPL","time_in_force":"day","trail_percent":null,"trail_price":null,
+    "type":"limit","updated_at":"2021-12-09T19:48:46.185346448Z"
+  }}
+}}"#,
+      event
+    );
+    json_from_str(&json).unwrap()
+  }
+
+
+  /// Check that updates are delivered to a channel and stay pending
+  /// until acknowledged.
+  #[test(tokio::test)]
+  async fn deliver_to_channel() {
+    let (send, mut recv) = unbounded();
+    let mut bridge = Bridge::new(send);
+    let acker = bridge.acker();
+
+    let updates = vec![Ok(Ok(update("new"))), Ok(Ok(update("fill")))];
+    let () = bridge.run(iter(updates)).await.unwrap();
+
+    let first = recv.next().await.unwrap();
+    assert_eq!(first.id, DeliveryId(1));
+    assert_eq!(first.attempt, 1);
+    assert_eq!(first.update.event, OrderStatus::New);
+
+    let second = recv.next().await.unwrap();
+    assert_eq!(second.id, DeliveryId(2));
+    assert_eq!(second.update.event, OrderStatus::Filled);
+    assert_eq!(bridge.pending(), 2);
+
+    acker.ack(first.id);
+    assert_eq!(bridge.pending(), 1);
+
+    let pending = bridge.into_pending();
+    assert_eq!(pending, vec![second]);
+  }
+
+  /// Check that unacknowledged deliveries are attempted again.
+  #[test(tokio::test)]
+  async fn redeliver_unacknowledged() {
+    let (send, mut recv) = channel(4);
+    let mut bridge = Bridge::new(send).with_redelivery_timeout(Duration::from_millis(10));
+    let acker = bridge.acker();
+
+    {
+      let updates = iter(vec![Ok(Ok(update("new")))]).chain(pending());
+      let run = bridge.run(updates);
+      let consume = async {
+        let first = recv.next().await.unwrap();
+        assert_eq!(first.attempt, 1);
+
+        let second = recv.next().await.unwrap();
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.attempt, 2);
+        acker.ack(second.id);
+      };
+
+      pin_mut!(run);
+      pin_mut!(consume);
+      match select(run, consume).await {
+        Either::Left((result, _)) => panic!("bridge stopped unexpectedly: {:?}", result),
+        Either::Right(((), _)) => (),
+      }
+    }
+
+    assert_eq!(bridge.pending(), 0);
+  }
+
+  /// Check that updates are delivered again if publishing them failed.
+  #[test(tokio::test)]
+  async fn redeliver_failed_publish() {
+    let (send, recv) = unbounded();
+    let publisher = Callback(move |delivery: Delivery| {
+      let result = if delivery.attempt == 1 {
+        Err("failed")
+      } else {
+        send.unbounded_send(delivery).map_err(|_| "send failed")
+      };
+      ready(result)
+    });
+    let mut bridge = Bridge::new(publisher).with_redelivery_timeout(Duration::from_millis(10));
+
+    let updates = iter(vec![Ok(Ok(update("canceled")))]).chain(pending());
+    let run = bridge.run(updates);
+    let consume = recv.into_future();
+
+    pin_mut!(run);
+    match select(run, consume).await {
+      Either::Left((result, _)) => panic!("bridge stopped unexpectedly: {:?}", result),
+      Either::Right(((delivery, _), _)) => {
+        let delivery = delivery.unwrap();
+        assert_eq!(delivery.attempt, 2);
+        assert_eq!(delivery.update.event, OrderStatus::Canceled);
+      },
+    }
+  }
+}
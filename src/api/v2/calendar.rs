@@ -3,8 +3,12 @@
 
 use std::ops::Range;
 
+use chrono::DateTime;
+use chrono::Duration;
 use chrono::NaiveDate;
 use chrono::NaiveTime;
+use chrono::TimeZone as _;
+use chrono::Utc;
 
 use serde::de::Error;
 use serde::de::Unexpected;
@@ -13,6 +17,8 @@ use serde::Deserializer;
 use serde::Serialize;
 use serde_urlencoded::to_string as to_query;
 
+use crate::util::is_dst;
+use crate::util::to_eastern;
 use crate::Str;
 
 
@@ -31,6 +37,18 @@ where
 }
 
 
+/// Convert a date and time in US Eastern time into UTC.
+fn from_eastern(date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+  let local = date.and_time(time);
+  let standard = Utc.from_utc_datetime(&(local + Duration::hours(5)));
+  if is_dst(&standard) {
+    standard - Duration::hours(1)
+  } else {
+    standard
+  }
+}
+
+
 /// The market open and close times for a specific date.
 #[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
 pub struct OpenClose {
@@ -45,6 +63,81 @@ pub struct OpenClose {
   pub close: NaiveTime,
 }
 
+impl OpenClose {
+  /// Retrieve the time the market opens at, in UTC.
+  #[inline]
+  pub fn open_utc(&self) -> DateTime<Utc> {
+    from_eastern(self.date, self.open)
+  }
+
+  /// Retrieve the time the market closes at, in UTC.
+  #[inline]
+  pub fn close_utc(&self) -> DateTime<Utc> {
+    from_eastern(self.date, self.close)
+  }
+}
+
+
+/// Market calendar data for a range of dates, for answering questions
+/// about market hours without the need to contact the API.
+///
+/// A `Calendar` is typically created from the result of a [`Get`]
+/// request covering the time span of interest. Note that the calendar
+/// only covers the regular trading session, including early closes,
+/// but not pre- or post-market hours.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Calendar {
+  /// The market days covered, sorted by date.
+  days: Vec<OpenClose>,
+}
+
+impl Calendar {
+  /// Create a `Calendar` from the provided market days.
+  pub fn new(mut days: Vec<OpenClose>) -> Self {
+    days.sort_by_key(|open_close| open_close.date);
+    days.dedup_by_key(|open_close| open_close.date);
+    Self { days }
+  }
+
+  /// Retrieve the market open and close times for the given date, if
+  /// the market is open on it.
+  pub fn day(&self, date: NaiveDate) -> Option<&OpenClose> {
+    self
+      .days
+      .binary_search_by_key(&date, |open_close| open_close.date)
+      .ok()
+      .map(|index| &self.days[index])
+  }
+
+  /// Check whether the market is open at the given time.
+  ///
+  /// `None` is returned if the time falls onto a date (in US Eastern
+  /// time) before the first or after the last market day covered by
+  /// the calendar, as no statement can be made in this case.
+  pub fn is_market_open_at(&self, time: &DateTime<Utc>) -> Option<bool> {
+    let local = to_eastern(time).naive_local();
+    let date = local.date();
+    let first = self.days.first()?;
+    let last = self.days.last()?;
+    if date < first.date || date > last.date {
+      return None
+    }
+
+    let open = self
+      .day(date)
+      .map(|open_close| open_close.open <= local.time() && local.time() < open_close.close)
+      .unwrap_or(false);
+    Some(open)
+  }
+}
+
+impl From<Vec<OpenClose>> for Calendar {
+  #[inline]
+  fn from(days: Vec<OpenClose>) -> Self {
+    Self::new(days)
+  }
+}
+
 
 /// A GET request to be made to the /v2/calendar endpoint.
 #[derive(Clone, Copy, Serialize, PartialEq, Debug)]
@@ -94,6 +187,8 @@ Endpoint! {
 mod tests {
   use super::*;
 
+  use std::str::FromStr as _;
+
   use crate::api_info::ApiInfo;
   use crate::Client;
 
@@ -116,6 +211,91 @@ mod tests {
     assert_eq!(open_close, expected);
   }
 
+  /// Check that we can convert market open and close times into UTC,
+  /// with daylight saving time being honored.
+  #[test]
+  fn open_close_utc() {
+    let open_close = OpenClose {
+      date: NaiveDate::from_ymd_opt(2022, 3, 11).unwrap(),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+    };
+    assert_eq!(
+      open_close.open_utc().to_rfc3339(),
+      "2022-03-11T14:30:00+00:00"
+    );
+    assert_eq!(
+      open_close.close_utc().to_rfc3339(),
+      "2022-03-11T21:00:00+00:00"
+    );
+
+    // The Monday after the switch to daylight saving time.
+    let open_close = OpenClose {
+      date: NaiveDate::from_ymd_opt(2022, 3, 14).unwrap(),
+      ..open_close
+    };
+    assert_eq!(
+      open_close.open_utc().to_rfc3339(),
+      "2022-03-14T13:30:00+00:00"
+    );
+
+    // An early close on the day after Thanksgiving.
+    let open_close = OpenClose {
+      date: NaiveDate::from_ymd_opt(2022, 11, 25).unwrap(),
+      close: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+      ..open_close
+    };
+    assert_eq!(
+      open_close.close_utc().to_rfc3339(),
+      "2022-11-25T18:00:00+00:00"
+    );
+  }
+
+  /// Check that we correctly convert UTC times into US Eastern time
+  /// around the daylight saving time transitions.
+  #[test]
+  fn convert_to_eastern() {
+    let time = DateTime::<Utc>::from_str("2022-03-13T06:59:00Z").unwrap();
+    assert_eq!(to_eastern(&time).to_rfc3339(), "2022-03-13T01:59:00-05:00");
+    let time = DateTime::<Utc>::from_str("2022-03-13T07:00:00Z").unwrap();
+    assert_eq!(to_eastern(&time).to_rfc3339(), "2022-03-13T03:00:00-04:00");
+    let time = DateTime::<Utc>::from_str("2022-11-06T05:59:00Z").unwrap();
+    assert_eq!(to_eastern(&time).to_rfc3339(), "2022-11-06T01:59:00-04:00");
+    let time = DateTime::<Utc>::from_str("2022-11-06T06:00:00Z").unwrap();
+    assert_eq!(to_eastern(&time).to_rfc3339(), "2022-11-06T01:00:00-05:00");
+  }
+
+  /// Check that we can determine whether the market is open at a
+  /// given time from calendar data.
+  #[test]
+  fn market_open_at() {
+    let day = |day, close| OpenClose {
+      date: NaiveDate::from_ymd_opt(2022, 11, day).unwrap(),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(close, 0, 0).unwrap(),
+    };
+    // Thanksgiving is on the 24th, with an early close on the day
+    // after.
+    let calendar = Calendar::from(vec![day(25, 13), day(23, 16), day(28, 16)]);
+    let is_open = |time| calendar.is_market_open_at(&DateTime::<Utc>::from_str(time).unwrap());
+
+    assert_eq!(is_open("2022-11-23T14:29:59Z"), Some(false));
+    assert_eq!(is_open("2022-11-23T14:30:00Z"), Some(true));
+    assert_eq!(is_open("2022-11-23T20:59:59Z"), Some(true));
+    assert_eq!(is_open("2022-11-23T21:00:00Z"), Some(false));
+    assert_eq!(is_open("2022-11-24T16:00:00Z"), Some(false));
+    assert_eq!(is_open("2022-11-25T17:59:59Z"), Some(true));
+    assert_eq!(is_open("2022-11-25T18:00:00Z"), Some(false));
+    assert_eq!(is_open("2022-11-26T16:00:00Z"), Some(false));
+    assert_eq!(is_open("2022-11-28T15:00:00Z"), Some(true));
+    // Times outside of the range covered are not answered.
+    assert_eq!(is_open("2022-11-22T16:00:00Z"), None);
+    assert_eq!(is_open("2022-11-29T16:00:00Z"), None);
+    // Late on the 28th in US Eastern time is still covered.
+    assert_eq!(is_open("2022-11-29T03:00:00Z"), Some(false));
+    assert_eq!(Calendar::default().is_market_open_at(&Utc::now()), None);
+  }
+
   /// Check that we error out as expected when failing to parse an
   /// `OpenClose` object because the time format is unexpected.
   #[test]
@@ -108,6 +108,26 @@ mod tests {
     }
   }
 
+  /// Check that we can retrieve the market clock into a custom type.
+  #[test(tokio::test)]
+  async fn current_market_clock_as() {
+    #[derive(Deserialize)]
+    struct MyClock {
+      #[serde(rename = "is_open")]
+      open: bool,
+      #[serde(rename = "timestamp")]
+      current: DateTime<Utc>,
+    }
+
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let clock = client.issue::<Get>(&()).await.unwrap();
+    let my_clock = client.issue_as::<Get, MyClock>(&()).await.unwrap();
+
+    assert!(my_clock.current >= clock.current);
+    assert!(my_clock.open == clock.open || my_clock.current > clock.current);
+  }
+
   /// Check that we get back the expected error when requesting the
   /// market clock with invalid credentials.
   #[test(tokio::test)]
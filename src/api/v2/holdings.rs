@@ -0,0 +1,251 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+use thiserror::Error;
+
+use crate::api::v2::account_activities;
+use crate::api::v2::ledger::export_between;
+use crate::api::v2::ledger::Category;
+use crate::api::v2::ledger::Entry;
+use crate::api::v2::position;
+use crate::api::v2::position::Position;
+use crate::api::v2::positions;
+use crate::Client;
+use crate::Pacer;
+use crate::Quantity;
+use crate::RequestError;
+
+
+/// The holdings of an account: the quantity held, by symbol.
+///
+/// Quantities of short positions are negative. Symbols with a quantity
+/// of zero are not contained.
+pub type Holdings = BTreeMap<String, Quantity>;
+
+
+/// An error as reported by [`holdings_on`].
+#[derive(Debug, Error)]
+pub enum HoldingsError {
+  /// The current positions could not be retrieved.
+  #[error("failed to retrieve positions")]
+  Positions(#[source] RequestError<positions::GetError>),
+  /// The account activities could not be retrieved.
+  #[error("failed to retrieve account activities")]
+  Activities(#[source] RequestError<account_activities::GetError>),
+}
+
+
+/// Retrieve the symbol and the change in quantity an entry represents,
+/// if it affects the holdings of the account.
+///
+/// Besides trades, security transfers, corporate actions (e.g., splits
+/// or symbol changes), and option related activities move securities.
+/// Alpaca reports these with one entry per symbol involved, with a
+/// negative quantity for shares leaving the account and a positive one
+/// for shares entering it.
+fn change(entry: &Entry) -> Option<(&str, &Quantity)> {
+  match entry.category {
+    Category::Trade | Category::SecurityTransfer | Category::CorporateAction | Category::Option => {
+      let symbol = entry.symbol.as_deref()?;
+      let quantity = entry.quantity.as_ref()?;
+      Some((symbol, quantity))
+    },
+    _ => None,
+  }
+}
+
+
+/// Add `quantity` to the holdings of `symbol`.
+fn add(holdings: &mut Holdings, symbol: &str, quantity: Quantity) {
+  let held = holdings.remove(symbol).unwrap_or_default() + quantity;
+  if !held.is_zero() {
+    let _prev = holdings.insert(symbol.to_string(), held);
+  }
+}
+
+
+/// Create the holdings corresponding to the provided positions.
+pub fn from_positions(positions: &[Position]) -> Holdings {
+  let mut holdings = Holdings::new();
+  for position in positions {
+    let quantity = match position.side {
+      position::Side::Long => position.quantity.clone(),
+      position::Side::Short => -&position.quantity,
+    };
+    let () = add(&mut holdings, &position.symbol, quantity);
+  }
+  holdings
+}
+
+
+/// Replay the provided ledger entries, in order, onto `holdings`.
+///
+/// Starting with empty holdings and the entries of the entire history
+/// of an account, the result reflects the holdings after the last
+/// entry.
+pub fn replay<'e, I>(mut holdings: Holdings, entries: I) -> Holdings
+where
+  I: IntoIterator<Item = &'e Entry>,
+{
+  for entry in entries {
+    if let Some((symbol, quantity)) = change(entry) {
+      let () = add(&mut holdings, symbol, quantity.clone());
+    }
+  }
+  holdings
+}
+
+
+/// Undo the provided ledger entries on `holdings`, in reverse order.
+///
+/// This is the inverse of [`replay`]: starting with the current
+/// holdings and all entries since a certain point in time, the result
+/// reflects the holdings just before the first entry.
+pub fn rewind<'e, I>(mut holdings: Holdings, entries: I) -> Holdings
+where
+  I: IntoIterator<Item = &'e Entry>,
+  I::IntoIter: DoubleEndedIterator,
+{
+  for entry in entries.into_iter().rev() {
+    if let Some((symbol, quantity)) = change(entry) {
+      let () = add(&mut holdings, symbol, -quantity);
+    }
+  }
+  holdings
+}
+
+
+/// Reconstruct the holdings of the account at the end of the given
+/// (UTC) date.
+///
+/// The holdings are determined by retrieving the current positions and
+/// rewinding all account activities dated after `date` and up to the
+/// time the positions were retrieved. As such, the activities of the
+/// entire history of the account do not need to be retrieved, but the
+/// accuracy of the result depends on Alpaca reporting all activities
+/// that moved securities.
+///
+/// If a [`Pacer`] is provided, it is consulted before retrieving each
+/// page of activities.
+pub async fn holdings_on(
+  client: &Client,
+  date: NaiveDate,
+  pacer: Option<&mut Pacer>,
+) -> Result<Holdings, HoldingsError> {
+  let positions = client
+    .issue::<positions::Get>(&())
+    .await
+    .map_err(HoldingsError::Positions)?;
+  // Activities after this point in time are not reflected in the
+  // positions and so must not be rewound.
+  let now = Utc::now();
+  let start = Utc.from_utc_datetime(&(date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap());
+  let entries = if start < now {
+    export_between(client, start..now, pacer)
+      .await
+      .map_err(HoldingsError::Activities)?
+  } else {
+    Vec::new()
+  };
+
+  let holdings = rewind(from_positions(&positions), &entries);
+  Ok(holdings)
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use serde_json::from_str as from_json;
+
+  use test_log::test;
+
+  use crate::api::v2::account_activities::Activity;
+  use crate::api_info::ApiInfo;
+
+
+  /// Create a ledger entry from the JSON representation of an activity.
+  fn entry(json: &str) -> Entry {
+    Entry::from(from_json::<Activity>(json).unwrap())
+  }
+
+  /// Create holdings from the given symbol and quantity pairs.
+  fn holdings(pairs: &[(&str, i64)]) -> Holdings {
+    pairs
+      .iter()
+      .map(|(symbol, quantity)| (symbol.to_string(), Quantity::from(*quantity)))
+      .collect()
+  }
+
+
+  /// Check that replaying and rewinding entries is symmetric and
+  /// accounts for corporate actions.
+  #[test]
+  fn replay_rewind() {
+    let entries = [
+      entry(
+        r#"{"activity_type":"FILL","cum_qty":"10","id":"1","leaves_qty":"0","price":"100",
+            "qty":"10","side":"buy","symbol":"FB","transaction_time":"2022-01-03T15:00:00Z",
+            "order_id":"904837e3-3b76-47ec-b432-046db621571b","type":"fill"}"#,
+      ),
+      entry(
+        r#"{"activity_type":"DIV","id":"2","date":"2022-02-01","net_amount":"5",
+            "symbol":"FB","qty":"10","per_share_amount":"0.5"}"#,
+      ),
+      entry(
+        r#"{"activity_type":"SC","id":"3","date":"2022-06-09","net_amount":"0",
+            "symbol":"FB","qty":"-10"}"#,
+      ),
+      entry(
+        r#"{"activity_type":"SC","id":"4","date":"2022-06-09","net_amount":"0",
+            "symbol":"META","qty":"10"}"#,
+      ),
+      entry(
+        r#"{"activity_type":"FILL","cum_qty":"4","id":"5","leaves_qty":"0","price":"150",
+            "qty":"4","side":"sell","symbol":"META","transaction_time":"2022-07-01T15:00:00Z",
+            "order_id":"904837e3-3b76-47ec-b432-046db621571b","type":"fill"}"#,
+      ),
+      entry(
+        r#"{"activity_type":"FILL","cum_qty":"3","id":"6","leaves_qty":"0","price":"400",
+            "qty":"3","side":"sell_short","symbol":"SPY",
+            "transaction_time":"2022-07-01T16:00:00Z",
+            "order_id":"904837e3-3b76-47ec-b432-046db621571b","type":"fill"}"#,
+      ),
+    ];
+
+    let current = replay(Holdings::new(), &entries);
+    assert_eq!(current, holdings(&[("META", 6), ("SPY", -3)]));
+
+    // Rewind to before the symbol change.
+    let past = rewind(current.clone(), &entries[2..]);
+    assert_eq!(past, holdings(&[("FB", 10)]));
+    assert_eq!(replay(past, &entries[2..]), current);
+
+    assert_eq!(rewind(current, &entries), Holdings::new());
+  }
+
+  /// Check that we can reconstruct the holdings of the account on a
+  /// past date.
+  #[test(tokio::test)]
+  async fn reconstruct_holdings() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let today = Utc::now().naive_utc().date();
+    let positions = client.issue::<positions::Get>(&()).await.unwrap();
+
+    // Rewinding nothing results in the current holdings.
+    let holdings = holdings_on(&client, today, None).await.unwrap();
+    assert_eq!(holdings, from_positions(&positions));
+
+    let date = today - Duration::days(30);
+    let _holdings = holdings_on(&client, date, None).await.unwrap();
+  }
+}
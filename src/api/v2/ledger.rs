@@ -0,0 +1,431 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::borrow::Cow;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::ops::Range;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::SecondsFormat;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+use num_decimal::Num;
+
+use serde::Serialize;
+use serde_json::to_writer as json_to_writer;
+use serde_variant::to_variant_name;
+
+use crate::api::v2::account_activities::Activity;
+use crate::api::v2::account_activities::ActivityReq;
+use crate::api::v2::account_activities::ActivityType;
+use crate::api::v2::account_activities::Direction;
+use crate::api::v2::account_activities::Get;
+use crate::api::v2::account_activities::GetError;
+use crate::api::v2::account_activities::Side;
+use crate::util::abs;
+use crate::Client;
+use crate::Notional;
+use crate::Pacer;
+use crate::Price;
+use crate::Quantity;
+use crate::RequestError;
+
+
+/// The header line of the CSV representation of a ledger.
+const CSV_HEADER: &str = "id,time,category,activity_type,symbol,quantity,price,amount,description";
+
+
+/// The category of a ledger [`Entry`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum Category {
+  /// The execution of a trade.
+  #[serde(rename = "trade")]
+  Trade,
+  /// A transfer of cash into or out of the account.
+  #[serde(rename = "cash_transfer")]
+  CashTransfer,
+  /// A transfer of securities into or out of the account.
+  #[serde(rename = "security_transfer")]
+  SecurityTransfer,
+  /// A dividend or other distribution.
+  #[serde(rename = "dividend")]
+  Dividend,
+  /// Interest credited or charged.
+  #[serde(rename = "interest")]
+  Interest,
+  /// Taxes withheld.
+  #[serde(rename = "tax")]
+  Tax,
+  /// Fees charged or rebated.
+  #[serde(rename = "fee")]
+  Fee,
+  /// A corporate action, such as a split or a merger.
+  #[serde(rename = "corporate_action")]
+  CorporateAction,
+  /// An option assignment, exercise, or expiration.
+  #[serde(rename = "option")]
+  Option,
+  /// Any other activity.
+  #[serde(rename = "other")]
+  Other,
+}
+
+impl From<ActivityType> for Category {
+  fn from(type_: ActivityType) -> Self {
+    match type_ {
+      ActivityType::Fill => Self::Trade,
+      ActivityType::Transaction
+      | ActivityType::AcatsInOutCash
+      | ActivityType::CashDeposit
+      | ActivityType::CashWithdrawal
+      | ActivityType::JournalEntry
+      | ActivityType::JournalEntryCash => Self::CashTransfer,
+      ActivityType::AcatsInOutSecurities | ActivityType::JournalEntryStock => {
+        Self::SecurityTransfer
+      },
+      ActivityType::Dividend
+      | ActivityType::CapitalGainLongTerm
+      | ActivityType::CapitalGainShortTerm
+      | ActivityType::DividendReturnOfCapital
+      | ActivityType::DividendTaxExtempt => Self::Dividend,
+      ActivityType::Interest => Self::Interest,
+      ActivityType::DividendAdjusted
+      | ActivityType::DividendAdjustedNraWithheld
+      | ActivityType::DividendAdjustedTefraWithheld
+      | ActivityType::InterestAdjustedNraWithheld
+      | ActivityType::InterestAdjustedTefraWithheld => Self::Tax,
+      ActivityType::DividendFee
+      | ActivityType::Fee
+      | ActivityType::PassThruCharge
+      | ActivityType::PassThruRebate => Self::Fee,
+      ActivityType::Acquisition
+      | ActivityType::NameChange
+      | ActivityType::Reorg
+      | ActivityType::SymbolChange
+      | ActivityType::StockSpinoff
+      | ActivityType::StockSplit => Self::CorporateAction,
+      ActivityType::OptionAssignment
+      | ActivityType::OptionExpiration
+      | ActivityType::OptionExercise => Self::Option,
+      ActivityType::Miscellaneous | ActivityType::Unknown => Self::Other,
+    }
+  }
+}
+
+
+/// Normalize the sign of the net amount of a non-trade activity.
+///
+/// Alpaca is not consistent in the signs it reports for certain
+/// activity types. For those that unambiguously credit or debit the
+/// account we enforce the respective sign, all others are left as
+/// reported.
+fn normalize_amount(type_: ActivityType, amount: Num) -> Num {
+  match type_ {
+    ActivityType::CashDeposit
+    | ActivityType::Dividend
+    | ActivityType::CapitalGainLongTerm
+    | ActivityType::CapitalGainShortTerm
+    | ActivityType::DividendReturnOfCapital
+    | ActivityType::DividendTaxExtempt
+    | ActivityType::PassThruRebate => abs(amount),
+    ActivityType::CashWithdrawal
+    | ActivityType::DividendFee
+    | ActivityType::DividendAdjusted
+    | ActivityType::DividendAdjustedNraWithheld
+    | ActivityType::DividendAdjustedTefraWithheld
+    | ActivityType::InterestAdjustedNraWithheld
+    | ActivityType::InterestAdjustedTefraWithheld
+    | ActivityType::Fee
+    | ActivityType::PassThruCharge => -abs(amount),
+    _ => amount,
+  }
+}
+
+
+/// An entry in a ledger of account activities.
+///
+/// All amounts are expressed from the perspective of the account: the
+/// `amount` is positive for cash flowing into the account (e.g., sales
+/// and dividends) and negative for cash flowing out of it (e.g.,
+/// purchases and fees). For trades, the `quantity` is positive for
+/// shares bought and negative for shares sold.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Entry {
+  /// The ID of the activity the entry was created from.
+  #[serde(rename = "id")]
+  pub id: String,
+  /// The time of the activity. For non-trade activities, which are
+  /// only reported with a date, this is midnight UTC of that date.
+  #[serde(rename = "time")]
+  pub time: DateTime<Utc>,
+  /// The category of the activity.
+  #[serde(rename = "category")]
+  pub category: Category,
+  /// The type of the activity, as reported by Alpaca.
+  #[serde(rename = "activity_type")]
+  pub activity_type: ActivityType,
+  /// The symbol of the security involved, if any.
+  #[serde(rename = "symbol")]
+  pub symbol: Option<String>,
+  /// The number of shares involved, if reported.
+  #[serde(rename = "quantity")]
+  pub quantity: Option<Quantity>,
+  /// The per-share price, if reported.
+  #[serde(rename = "price")]
+  pub price: Option<Price>,
+  /// The amount of cash moved.
+  #[serde(rename = "amount")]
+  pub amount: Notional,
+  /// A description of the activity, if any.
+  #[serde(rename = "description")]
+  pub description: Option<String>,
+}
+
+impl From<Activity> for Entry {
+  fn from(activity: Activity) -> Self {
+    match activity {
+      Activity::Trade(trade) => {
+        let quantity = match trade.side {
+          Side::Buy => Quantity(trade.quantity),
+          Side::Sell | Side::ShortSell => -Quantity(trade.quantity),
+        };
+        let price = Price(trade.price);
+        let amount = -(&quantity * &price);
+
+        Self {
+          id: trade.id,
+          time: trade.transaction_time,
+          category: Category::Trade,
+          activity_type: ActivityType::Fill,
+          symbol: Some(trade.symbol),
+          quantity: Some(quantity),
+          price: Some(price),
+          amount,
+          description: None,
+        }
+      },
+      Activity::NonTrade(non_trade) => Self {
+        id: non_trade.id,
+        time: non_trade.date,
+        category: Category::from(non_trade.type_),
+        activity_type: non_trade.type_,
+        symbol: non_trade.symbol,
+        quantity: non_trade.quantity.map(Quantity),
+        price: non_trade.price.map(Price),
+        amount: Notional(normalize_amount(non_trade.type_, non_trade.net_amount)),
+        description: non_trade.description,
+      },
+    }
+  }
+}
+
+
+/// Retrieve all account activities dated within the given range of
+/// (UTC) dates, in ascending order, and convert them into ledger
+/// entries.
+///
+/// If a [`Pacer`] is provided, it is consulted before retrieving each
+/// page of activities.
+pub async fn export(
+  client: &Client,
+  dates: Range<NaiveDate>,
+  pacer: Option<&mut Pacer>,
+) -> Result<Vec<Entry>, RequestError<GetError>> {
+  let start = Utc.from_utc_datetime(&dates.start.and_hms_opt(0, 0, 0).unwrap());
+  let end = Utc.from_utc_datetime(&dates.end.and_hms_opt(0, 0, 0).unwrap());
+  export_between(client, start..end, pacer).await
+}
+
+
+/// Retrieve all account activities dated within the given range of
+/// times, in ascending order, and convert them into ledger entries.
+pub(crate) async fn export_between(
+  client: &Client,
+  times: Range<DateTime<Utc>>,
+  pacer: Option<&mut Pacer>,
+) -> Result<Vec<Entry>, RequestError<GetError>> {
+  let Range { start, end } = times;
+  // The `after` bound is exclusive while `until` is inclusive. We make
+  // sure to capture activities dated on the very start of the range
+  // and filter out anything reported in excess below.
+  let request = ActivityReq {
+    direction: Direction::Ascending,
+    after: Some(start - Duration::seconds(1)),
+    until: Some(end),
+    ..Default::default()
+  };
+
+  let activities = client.issue_paginated::<Get>(&request, pacer).await?;
+  let entries = activities
+    .into_iter()
+    .filter(|activity| start <= *activity.time() && *activity.time() < end)
+    .map(Entry::from)
+    .collect();
+  Ok(entries)
+}
+
+
+/// Escape a field for inclusion in a CSV record, if necessary.
+fn csv_field(field: &str) -> Cow<'_, str> {
+  if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+    Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+  } else {
+    Cow::Borrowed(field)
+  }
+}
+
+
+/// Write the provided ledger entries as CSV, including a header line.
+pub fn write_csv<W>(mut writer: W, entries: &[Entry]) -> IoResult<()>
+where
+  W: Write,
+{
+  writeln!(writer, "{}", CSV_HEADER)?;
+
+  for entry in entries {
+    // Both enums consist of unit variants only and so serialization
+    // cannot fail.
+    let category = to_variant_name(&entry.category).unwrap();
+    let activity_type = to_variant_name(&entry.activity_type).unwrap();
+    let quantity = entry.quantity.as_ref().map(ToString::to_string);
+    let price = entry.price.as_ref().map(ToString::to_string);
+
+    writeln!(
+      writer,
+      "{},{},{},{},{},{},{},{},{}",
+      csv_field(&entry.id),
+      entry.time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+      category,
+      activity_type,
+      csv_field(entry.symbol.as_deref().unwrap_or("")),
+      quantity.as_deref().unwrap_or(""),
+      price.as_deref().unwrap_or(""),
+      entry.amount,
+      csv_field(entry.description.as_deref().unwrap_or("")),
+    )?;
+  }
+  Ok(())
+}
+
+
+/// Write the provided ledger entries as JSON Lines, i.e., with one
+/// JSON object per line.
+pub fn write_jsonl<W>(mut writer: W, entries: &[Entry]) -> IoResult<()>
+where
+  W: Write,
+{
+  for entry in entries {
+    let () = json_to_writer(&mut writer, entry)?;
+    writeln!(writer)?;
+  }
+  Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::from_utf8;
+
+  use serde_json::from_str as from_json;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+
+
+  /// Parse an activity from its JSON representation.
+  fn activity(json: &str) -> Activity {
+    from_json::<Activity>(json).unwrap()
+  }
+
+
+  /// Check that we normalize signs and categories of activities.
+  #[test]
+  fn normalize_activities() {
+    let buy = activity(
+      r#"{"activity_type":"FILL","cum_qty":"2","id":"1","leaves_qty":"0","price":"1.5",
+          "qty":"2","side":"buy","symbol":"SPY","transaction_time":"2022-01-03T15:00:00Z",
+          "order_id":"904837e3-3b76-47ec-b432-046db621571b","type":"fill"}"#,
+    );
+    let entry = Entry::from(buy);
+    assert_eq!(entry.category, Category::Trade);
+    assert_eq!(entry.quantity, Some(Quantity::from(2)));
+    assert_eq!(entry.amount, Notional::from(-3));
+
+    let sell = activity(
+      r#"{"activity_type":"FILL","cum_qty":"2","id":"2","leaves_qty":"0","price":"1.5",
+          "qty":"-2","side":"sell","symbol":"SPY","transaction_time":"2022-01-04T15:00:00Z",
+          "order_id":"904837e3-3b76-47ec-b432-046db621571b","type":"fill"}"#,
+    );
+    let entry = Entry::from(sell);
+    assert_eq!(entry.quantity, Some(Quantity::from(-2)));
+    assert_eq!(entry.amount, Notional::from(3));
+
+    let withdrawal =
+      activity(r#"{"activity_type":"CSW","id":"3","date":"2022-01-05","net_amount":"100"}"#);
+    let entry = Entry::from(withdrawal);
+    assert_eq!(entry.category, Category::CashTransfer);
+    assert_eq!(entry.amount, Notional::from(-100));
+
+    let tax =
+      activity(r#"{"activity_type":"DIVNRA","id":"4","date":"2022-01-05","net_amount":"-1"}"#);
+    let entry = Entry::from(tax);
+    assert_eq!(entry.category, Category::Tax);
+    assert_eq!(entry.amount, Notional::from(-1));
+
+    let interest =
+      activity(r#"{"activity_type":"INT","id":"5","date":"2022-01-05","net_amount":"-2"}"#);
+    let entry = Entry::from(interest);
+    assert_eq!(entry.category, Category::Interest);
+    assert_eq!(entry.amount, Notional::from(-2));
+  }
+
+  /// Check that we can write ledger entries as CSV and JSON Lines.
+  #[test]
+  fn write_ledger() {
+    let dividend = activity(
+      r#"{"activity_type":"DIV","id":"1","date":"2022-01-05","net_amount":"1.02",
+          "symbol":"T","description":"Cash DIV @ 0.51, \"T\""}"#,
+    );
+    let entries = [Entry::from(dividend)];
+
+    let mut csv = Vec::new();
+    let () = write_csv(&mut csv, &entries).unwrap();
+    let expected = r#"id,time,category,activity_type,symbol,quantity,price,amount,description
+1,2022-01-05T00:00:00Z,dividend,DIV,T,,,1.02,"Cash DIV @ 0.51, ""T"""
+"#;
+    assert_eq!(from_utf8(&csv).unwrap(), expected);
+
+    let mut jsonl = Vec::new();
+    let () = write_jsonl(&mut jsonl, &entries).unwrap();
+    let expected = r#"{"id":"1","time":"2022-01-05T00:00:00Z","category":"dividend","activity_type":"DIV","symbol":"T","quantity":null,"price":null,"amount":"1.02","description":"Cash DIV @ 0.51, \"T\""}
+"#;
+    assert_eq!(from_utf8(&jsonl).unwrap(), expected);
+  }
+
+  /// Check that we can export account activities for a range of dates.
+  #[test(tokio::test)]
+  async fn export_activities() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let start = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+    let end = Utc::now().naive_utc().date();
+    let entries = export(&client, start..end, None).await.unwrap();
+
+    let mut iter = entries.iter();
+    if let Some(first) = iter.next() {
+      let mut time = first.time;
+      for entry in iter {
+        assert!(time <= entry.time);
+        time = entry.time;
+      }
+    }
+  }
+}
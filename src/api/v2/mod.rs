@@ -11,12 +11,19 @@ pub mod account_config;
 pub mod asset;
 /// Functionality for listing available assets.
 pub mod assets;
+/// Functionality for republishing trade related updates.
+#[cfg(feature = "streaming")]
+pub mod bridge;
 /// Functionality for retrieving market open/close timing information
 /// for specific dates.
 pub mod calendar;
 /// Functionality for retrieving market open/close timing information
 /// for the current trading day.
 pub mod clock;
+/// Functionality for reconstructing historical position holdings.
+pub mod holdings;
+/// Functionality for exporting account activities as a ledger.
+pub mod ledger;
 /// Definitions surrounding orders.
 pub mod order;
 /// Functionality for listing orders.
@@ -25,7 +32,14 @@ pub mod orders;
 pub mod position;
 /// Functionality for listing open positions.
 pub mod positions;
+/// Functionality for planning trades to rebalance a portfolio.
+pub mod rebalance;
+/// Functionality for supervising order activity and shutting down
+/// gracefully.
+#[cfg(feature = "streaming")]
+pub mod supervisor;
 /// Definitions for trade related updates.
+#[cfg(feature = "streaming")]
 pub mod updates;
 /// Definitions surrounding watchlists.
 pub mod watchlist;
@@ -1,12 +1,17 @@
 // Copyright (C) 2019-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::num::NonZeroUsize;
 use std::ops::Deref;
 use std::ops::Not;
+use std::time::Duration;
 
 use chrono::DateTime;
 use chrono::Utc;
 
+use futures::stream::iter;
+use futures::StreamExt as _;
+
 use http::Method;
 use http_endpoint::Bytes;
 
@@ -20,10 +25,20 @@ use serde_json::from_slice as from_json;
 use serde_json::to_vec as to_json;
 use serde_urlencoded::to_string as to_query;
 
+use thiserror::Error;
+
+use tokio::time::sleep;
+use tokio::time::Instant;
+
 use uuid::Uuid;
 
 use crate::api::v2::asset;
 use crate::util::vec_from_str;
+use crate::Client;
+use crate::Notional;
+use crate::Price;
+use crate::Quantity;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -160,6 +175,35 @@ impl Not for Side {
 }
 
 
+/// The intent of an order with respect to the position it affects.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum PositionIntent {
+  /// Buy in order to open a long position or to increase it.
+  #[serde(rename = "buy_to_open")]
+  BuyToOpen,
+  /// Buy in order to cover (i.e., reduce or close) a short position.
+  #[serde(rename = "buy_to_close")]
+  BuyToClose,
+  /// Sell in order to open a short position or to increase it.
+  #[serde(rename = "sell_to_open")]
+  SellToOpen,
+  /// Sell in order to reduce or close a long position.
+  #[serde(rename = "sell_to_close")]
+  SellToClose,
+}
+
+impl PositionIntent {
+  /// Retrieve the side that an order with this intent is on.
+  #[inline]
+  pub fn side(self) -> Side {
+    match self {
+      Self::BuyToOpen | Self::BuyToClose => Side::Buy,
+      Self::SellToOpen | Self::SellToClose => Side::Sell,
+    }
+  }
+}
+
+
 /// The class an order belongs to.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
 pub enum Class {
@@ -254,7 +298,7 @@ impl Default for TimeInForce {
 #[serde(rename = "take_profit")]
 struct TakeProfitSerde {
   #[serde(rename = "limit_price")]
-  limit_price: Num,
+  limit_price: Price,
 }
 
 
@@ -264,7 +308,7 @@ struct TakeProfitSerde {
 #[serde(from = "TakeProfitSerde", into = "TakeProfitSerde")]
 pub enum TakeProfit {
   /// The limit price to use.
-  Limit(Num),
+  Limit(Price),
 }
 
 impl From<TakeProfitSerde> for TakeProfit {
@@ -286,9 +330,9 @@ impl From<TakeProfit> for TakeProfitSerde {
 #[serde(rename = "stop_loss")]
 struct StopLossSerde {
   #[serde(rename = "stop_price")]
-  stop_price: Num,
+  stop_price: Price,
   #[serde(rename = "limit_price", skip_serializing_if = "Option::is_none")]
-  limit_price: Option<Num>,
+  limit_price: Option<Price>,
 }
 
 
@@ -298,9 +342,9 @@ struct StopLossSerde {
 #[serde(from = "StopLossSerde", into = "StopLossSerde")]
 pub enum StopLoss {
   /// The stop loss price to use.
-  Stop(Num),
+  Stop(Price),
   /// The stop loss and stop limit price to use.
-  StopLimit(Num, Num),
+  StopLimit(Price, Price),
 }
 
 impl From<StopLossSerde> for StopLoss {
@@ -338,21 +382,21 @@ pub enum Amount {
     /// A number of shares to order. This can be a fractional number if
     /// trading fractionals or a whole number if not.
     #[serde(rename = "qty")]
-    quantity: Num,
+    quantity: Quantity,
   },
   /// Wrapper for the notional field.
   Notional {
     /// A dollar amount to use for the order. This can result in
     /// fractional quantities.
     #[serde(rename = "notional")]
-    notional: Num,
+    notional: Notional,
   },
 }
 
 impl Amount {
   /// Helper method to initialize a quantity.
   #[inline]
-  pub fn quantity(amount: impl Into<Num>) -> Self {
+  pub fn quantity(amount: impl Into<Quantity>) -> Self {
     Self::Quantity {
       quantity: amount.into(),
     }
@@ -360,7 +404,7 @@ impl Amount {
 
   /// Helper method to initialize a notional.
   #[inline]
-  pub fn notional(amount: impl Into<Num>) -> Self {
+  pub fn notional(amount: impl Into<Notional>) -> Self {
     Self::Notional {
       notional: amount.into(),
     }
@@ -378,11 +422,11 @@ pub struct OrderReqInit {
   /// See `OrderReq::time_in_force`.
   pub time_in_force: TimeInForce,
   /// See `OrderReq::limit_price`.
-  pub limit_price: Option<Num>,
+  pub limit_price: Option<Price>,
   /// See `OrderReq::stop_price`.
-  pub stop_price: Option<Num>,
+  pub stop_price: Option<Price>,
   /// See `OrderReq::trail_price`.
-  pub trail_price: Option<Num>,
+  pub trail_price: Option<Price>,
   /// See `OrderReq::trail_percent`.
   pub trail_percent: Option<Num>,
   /// See `OrderReq::take_profit`.
@@ -393,6 +437,8 @@ pub struct OrderReqInit {
   pub extended_hours: bool,
   /// See `OrderReq::client_order_id`.
   pub client_order_id: Option<String>,
+  /// See `OrderReq::position_intent`.
+  pub position_intent: Option<PositionIntent>,
   #[doc(hidden)]
   pub _non_exhaustive: (),
 }
@@ -423,6 +469,7 @@ impl OrderReqInit {
       client_order_id: self.client_order_id,
       trail_price: self.trail_price,
       trail_percent: self.trail_percent,
+      position_intent: self.position_intent,
     }
   }
 }
@@ -451,13 +498,13 @@ pub struct OrderReq {
   pub time_in_force: TimeInForce,
   /// The limit price.
   #[serde(rename = "limit_price")]
-  pub limit_price: Option<Num>,
+  pub limit_price: Option<Price>,
   /// The stop price.
   #[serde(rename = "stop_price")]
-  pub stop_price: Option<Num>,
+  pub stop_price: Option<Price>,
   /// The dollar value away from the high water mark.
   #[serde(rename = "trail_price")]
-  pub trail_price: Option<Num>,
+  pub trail_price: Option<Price>,
   /// The percent value away from the high water mark.
   #[serde(rename = "trail_percent")]
   pub trail_percent: Option<Num>,
@@ -482,6 +529,13 @@ pub struct OrderReq {
   /// The documented maximum length is 48 characters.
   #[serde(rename = "client_order_id")]
   pub client_order_id: Option<String>,
+  /// The intent of the order with respect to the position it affects,
+  /// if any. It has to agree with the order's side.
+  ///
+  /// If not provided, the intent is inferred by Alpaca based on the
+  /// current position.
+  #[serde(rename = "position_intent", skip_serializing_if = "Option::is_none")]
+  pub position_intent: Option<PositionIntent>,
 }
 
 
@@ -489,13 +543,13 @@ pub struct OrderReq {
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct ChangeReqInit {
   /// See `ChangeReq::quantity`.
-  pub quantity: Num,
+  pub quantity: Quantity,
   /// See `ChangeReq::time_in_force`.
   pub time_in_force: TimeInForce,
   /// See `ChangeReq::limit_price`.
-  pub limit_price: Option<Num>,
+  pub limit_price: Option<Price>,
   /// See `ChangeReq::stop_price`.
-  pub stop_price: Option<Num>,
+  pub stop_price: Option<Price>,
   /// See `ChangeReq::trail`.
   pub trail: Option<Num>,
   #[doc(hidden)]
@@ -521,16 +575,16 @@ impl ChangeReqInit {
 pub struct ChangeReq {
   /// Number of shares to trade.
   #[serde(rename = "qty")]
-  pub quantity: Num,
+  pub quantity: Quantity,
   /// How long the order will be valid.
   #[serde(rename = "time_in_force")]
   pub time_in_force: TimeInForce,
   /// The limit price.
   #[serde(rename = "limit_price")]
-  pub limit_price: Option<Num>,
+  pub limit_price: Option<Price>,
   /// The stop price.
   #[serde(rename = "stop_price")]
-  pub stop_price: Option<Num>,
+  pub stop_price: Option<Price>,
   /// The new value of the `trail_price` or `trail_percent` value.
   #[serde(rename = "trail")]
   pub trail: Option<Num>,
@@ -599,7 +653,7 @@ pub struct Order {
   pub amount: Amount,
   /// The quantity that was filled.
   #[serde(rename = "filled_qty")]
-  pub filled_quantity: Num,
+  pub filled_quantity: Quantity,
   /// The type of order.
   #[serde(rename = "type")]
   pub type_: Type,
@@ -614,23 +668,27 @@ pub struct Order {
   pub time_in_force: TimeInForce,
   /// The limit price.
   #[serde(rename = "limit_price")]
-  pub limit_price: Option<Num>,
+  pub limit_price: Option<Price>,
   /// The stop price.
   #[serde(rename = "stop_price")]
-  pub stop_price: Option<Num>,
+  pub stop_price: Option<Price>,
   /// The dollar value away from the high water mark.
   #[serde(rename = "trail_price")]
-  pub trail_price: Option<Num>,
+  pub trail_price: Option<Price>,
   /// The percent value away from the high water mark.
   #[serde(rename = "trail_percent")]
   pub trail_percent: Option<Num>,
   /// The average price at which the order was filled.
   #[serde(rename = "filled_avg_price")]
-  pub average_fill_price: Option<Num>,
+  pub average_fill_price: Option<Price>,
   /// If true, the order is eligible for execution outside regular
   /// trading hours.
   #[serde(rename = "extended_hours")]
   pub extended_hours: bool,
+  /// The intent of the order with respect to the position it affects,
+  /// if reported.
+  #[serde(rename = "position_intent")]
+  pub position_intent: Option<PositionIntent>,
   /// Additional legs of the order.
   ///
   /// Such an additional leg could be, for example, the order for the
@@ -811,6 +869,326 @@ EndpointNoParse! {
 }
 
 
+/// The parameters for waiting for an order cancellation to be
+/// confirmed, as used by [`cancel`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Confirmation {
+  /// The interval at which to poll the order's status.
+  pub interval: Duration,
+  /// The time after which to stop polling.
+  pub timeout: Duration,
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl Default for Confirmation {
+  fn default() -> Self {
+    Self {
+      interval: Duration::from_millis(500),
+      timeout: Duration::from_secs(30),
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// An error as reported by [`cancel`].
+#[derive(Debug, Error)]
+pub enum CancelError {
+  /// The cancellation request failed.
+  ///
+  /// Among others, this variant covers the order not being found
+  /// ([`DeleteError::NotFound`]) and the order no longer being
+  /// cancelable ([`DeleteError::NotCancelable`]).
+  #[error("failed to cancel order")]
+  Delete(#[source] RequestError<DeleteError>),
+  /// Retrieving the order while waiting for the cancellation to be
+  /// confirmed failed.
+  #[error("failed to retrieve order")]
+  Get(#[source] RequestError<GetError>),
+  /// The order reached a terminal status other than
+  /// [`Status::Canceled`], e.g., because it got filled before the
+  /// cancellation took effect.
+  #[error("order ended up {:?} instead of canceled", .0.status)]
+  NotCanceled(Box<Order>),
+  /// The cancellation was not confirmed in time. The contained order
+  /// is the order as last retrieved.
+  #[error("timed out waiting for order cancellation to be confirmed")]
+  Timeout(Box<Order>),
+}
+
+impl CancelError {
+  /// Check whether the error indicates that the order could not be
+  /// found.
+  #[inline]
+  pub fn is_not_found(&self) -> bool {
+    matches!(
+      self,
+      Self::Delete(RequestError::Endpoint(DeleteError::NotFound(..)))
+    )
+  }
+
+  /// Check whether the error indicates that the order can no longer
+  /// be canceled.
+  #[inline]
+  pub fn is_not_cancelable(&self) -> bool {
+    matches!(
+      self,
+      Self::Delete(RequestError::Endpoint(DeleteError::NotCancelable(..)))
+    )
+  }
+}
+
+
+/// Cancel the order with the given ID.
+///
+/// A successful [`Delete`] request merely means that the cancellation
+/// was accepted, not that the order is no longer active: it may still
+/// get (partially) filled in the meantime. If `confirm` is provided,
+/// this function polls the order until it reached a terminal status
+/// and returns it if it was canceled.
+pub async fn cancel(
+  client: &Client,
+  id: &Id,
+  confirm: Option<Confirmation>,
+) -> Result<Option<Order>, CancelError> {
+  let () = client
+    .issue::<Delete>(id)
+    .await
+    .map_err(CancelError::Delete)?;
+
+  let confirm = match confirm {
+    Some(confirm) => confirm,
+    None => return Ok(None),
+  };
+
+  let deadline = Instant::now() + confirm.timeout;
+  loop {
+    let order = client.issue::<Get>(id).await.map_err(CancelError::Get)?;
+    match order.status {
+      Status::Canceled => break Ok(Some(order)),
+      status if status.is_terminal() => break Err(CancelError::NotCanceled(Box::new(order))),
+      _ => (),
+    }
+
+    if Instant::now() + confirm.interval > deadline {
+      break Err(CancelError::Timeout(Box::new(order)))
+    }
+    sleep(confirm.interval).await;
+  }
+}
+
+
+/// Check whether a request error is of a transient nature, i.e.,
+/// whether retrying the request may succeed.
+macro_rules! is_transient {
+  ($err:expr, $type:ident) => {
+    match $err {
+      RequestError::Endpoint($type::RateLimitExceeded(..)) => true,
+      RequestError::Endpoint($type::UnexpectedStatus(status, _)) => status.is_server_error(),
+      RequestError::Endpoint(..) => false,
+      RequestError::Hyper(..) | RequestError::Io(..) => true,
+    }
+  };
+}
+
+
+/// The parameters for retrying failed order submissions, as used by
+/// [`submit_all`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Retry {
+  /// The maximum number of attempts to make per order, including the
+  /// first one. A value of one disables retries.
+  pub attempts: usize,
+  /// The delay before the first retry. The delay doubles with every
+  /// subsequent retry.
+  pub backoff: Duration,
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl Default for Retry {
+  fn default() -> Self {
+    Self {
+      attempts: 3,
+      backoff: Duration::from_millis(500),
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// An error as reported for an individual order by [`submit_all`].
+#[derive(Debug, Error)]
+pub enum SubmitError {
+  /// The order submission failed.
+  #[error("failed to submit order")]
+  Post(#[source] RequestError<PostError>),
+  /// Checking whether a previous, seemingly failed, attempt at
+  /// submitting the order actually succeeded failed.
+  #[error("failed to check for previously submitted order")]
+  GetByClientId(#[source] RequestError<GetByClientIdError>),
+  /// The order submission failed in a way that leaves it unclear
+  /// whether the order was accepted, and checking for it failed as
+  /// well. The order should be looked up by its client order ID before
+  /// acting on the assumption that it was not placed.
+  #[error("order submission failed ambiguously")]
+  Ambiguous(#[source] RequestError<PostError>),
+}
+
+
+/// The outcome of submitting a single order via [`submit_all`].
+#[derive(Debug)]
+pub struct Submission {
+  /// The order request as submitted, with its client order ID set.
+  pub request: OrderReq,
+  /// The number of attempts made.
+  pub attempts: usize,
+  /// The result of the submission.
+  pub result: Result<Order, SubmitError>,
+}
+
+
+/// Make sure that an order request has a client order ID set.
+fn ensure_client_order_id(mut request: OrderReq) -> OrderReq {
+  if request.client_order_id.is_none() {
+    request.client_order_id = Some(Uuid::new_v4().as_simple().to_string());
+  }
+  request
+}
+
+
+/// Check whether a failed order submission may have succeeded despite
+/// the error.
+fn is_ambiguous(err: &RequestError<PostError>) -> bool {
+  // A rate limited request was rejected for sure, but in all other
+  // cases of transient errors the order may very well have been
+  // accepted.
+  is_transient!(err, PostError)
+    && !matches!(
+      err,
+      RequestError::Endpoint(PostError::RateLimitExceeded(..))
+    )
+}
+
+
+/// Check whether a failed order submission may have been rejected only
+/// because an earlier, ambiguously failed, attempt did in fact succeed.
+fn may_be_duplicate(err: &RequestError<PostError>, ambiguous: bool) -> bool {
+  // Alpaca rejects a client order ID that is already in use as invalid
+  // input.
+  ambiguous && matches!(err, RequestError::Endpoint(PostError::InvalidInput(..)))
+}
+
+
+/// Check whether an order with the given client order ID was accepted
+/// after all, despite its submission having failed with `err`.
+async fn check_submitted(
+  client: &Client,
+  client_order_id: &str,
+  err: RequestError<PostError>,
+) -> Result<Order, SubmitError> {
+  let client_order_id = client_order_id.to_string();
+  match client.issue::<GetByClientId>(&client_order_id).await {
+    Ok(order) => Ok(order),
+    Err(RequestError::Endpoint(GetByClientIdError::NotFound(..))) => Err(SubmitError::Post(err)),
+    Err(_) => Err(SubmitError::Ambiguous(err)),
+  }
+}
+
+
+/// Submit a single order, retrying on transient errors.
+async fn submit(client: &Client, request: OrderReq, retry: Retry) -> Submission {
+  let request = ensure_client_order_id(request);
+  // Unwrapping is fine, as we just made sure that a client order ID is
+  // present.
+  let client_order_id = request.client_order_id.clone().unwrap();
+  let mut backoff = retry.backoff;
+  let mut attempts = 0;
+  // Whether any previous attempt may have succeeded despite reporting
+  // an error.
+  let mut ambiguous = false;
+
+  let result = loop {
+    attempts += 1;
+
+    if ambiguous {
+      match client.issue::<GetByClientId>(&client_order_id).await {
+        Ok(order) => break Ok(order),
+        // The order was not accepted, so submit it again.
+        Err(RequestError::Endpoint(GetByClientIdError::NotFound(..))) => (),
+        Err(err) if attempts < retry.attempts && is_transient!(&err, GetByClientIdError) => {
+          sleep(backoff).await;
+          backoff *= 2;
+          continue
+        },
+        Err(err) => break Err(SubmitError::GetByClientId(err)),
+      }
+    }
+
+    match client.issue::<Post>(&request).await {
+      Ok(order) => break Ok(order),
+      Err(err) if attempts < retry.attempts && is_transient!(&err, PostError) => {
+        ambiguous = ambiguous || is_ambiguous(&err);
+        sleep(backoff).await;
+        backoff *= 2;
+      },
+      Err(err) if is_ambiguous(&err) => {
+        // We are out of attempts, but the order may still have been
+        // accepted. Check one last time before reporting a failure.
+        break check_submitted(client, &client_order_id, err).await
+      },
+      Err(err) if may_be_duplicate(&err, ambiguous) => {
+        // An earlier attempt may have been accepted without the order
+        // having been visible when we checked for it.
+        break check_submitted(client, &client_order_id, err).await
+      },
+      Err(err) => break Err(SubmitError::Post(err)),
+    }
+  };
+
+  Submission {
+    request,
+    attempts,
+    result,
+  }
+}
+
+
+/// Submit a batch of orders, with up to `concurrency` submissions in
+/// flight at any time.
+///
+/// Each order request lacking a client order ID is assigned a random
+/// one. Submissions failing for transient reasons (e.g., because the
+/// rate limit was hit or because of a connection issue) are retried
+/// as per `retry`. Before resubmitting an order after a failure that
+/// leaves it unclear whether the order was accepted, the client order
+/// ID is used for checking whether that is the case, so that no order
+/// is submitted twice. The same check is performed after the last
+/// attempt failed in such a way, with an error of
+/// [`SubmitError::Ambiguous`] reported if it fails as well, and when a
+/// resubmission is rejected as invalid, as happens when an earlier
+/// attempt was accepted but not yet visible.
+///
+/// The returned submissions are in the same order as the provided
+/// requests.
+pub async fn submit_all<I>(
+  client: &Client,
+  orders: I,
+  concurrency: NonZeroUsize,
+  retry: Retry,
+) -> Vec<Submission>
+where
+  I: IntoIterator<Item = OrderReq>,
+{
+  iter(orders)
+    .map(|request| submit(client, request, retry))
+    .buffered(concurrency.get())
+    .collect()
+    .await
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -820,6 +1198,7 @@ mod tests {
   use futures::TryFutureExt;
 
   use serde_json::from_slice as from_json;
+  use serde_json::Value as JsonValue;
 
   use test_log::test;
 
@@ -859,17 +1238,17 @@ mod tests {
   /// Make sure that we can serialize and deserialize order legs.
   #[test]
   fn serialize_deserialize_legs() {
-    let take_profit = TakeProfit::Limit(Num::new(3, 2));
+    let take_profit = TakeProfit::Limit(Price::from(Num::new(3, 2)));
     let json = to_json(&take_profit).unwrap();
     assert_eq!(json, br#"{"limit_price":"1.5"}"#);
     assert_eq!(from_json::<TakeProfit>(&json).unwrap(), take_profit);
 
-    let stop_loss = StopLoss::Stop(Num::from(42));
+    let stop_loss = StopLoss::Stop(Price::from(42));
     let json = to_json(&stop_loss).unwrap();
     assert_eq!(json, br#"{"stop_price":"42"}"#);
     assert_eq!(from_json::<StopLoss>(&json).unwrap(), stop_loss);
 
-    let stop_loss = StopLoss::StopLimit(Num::from(13), Num::from(96));
+    let stop_loss = StopLoss::StopLimit(Price::from(13), Price::from(96));
     let json = to_json(&stop_loss).unwrap();
     let expected = br#"{"stop_price":"13","limit_price":"96"}"#;
     assert_eq!(json, &expected[..]);
@@ -938,9 +1317,12 @@ mod tests {
     assert_eq!(order.type_, Type::Market);
     assert_eq!(order.class, Class::OneTriggersOther);
     assert_eq!(order.time_in_force, TimeInForce::Day);
-    assert_eq!(order.limit_price, Some(Num::from(107)));
-    assert_eq!(order.stop_price, Some(Num::from(106)));
-    assert_eq!(order.average_fill_price, Some(Num::new(10625, 100)));
+    assert_eq!(order.limit_price, Some(Price::from(107)));
+    assert_eq!(order.stop_price, Some(Price::from(106)));
+    assert_eq!(
+      order.average_fill_price,
+      Some(Price::from(Num::new(10625, 100)))
+    );
   }
 
   /// Verify that we can deserialize an order with an empty order class.
@@ -978,6 +1360,63 @@ mod tests {
 
     let order = from_json::<Order>(json).unwrap();
     assert_eq!(order.class, Class::Simple);
+    assert_eq!(order.position_intent, None);
+  }
+
+  /// Check that we can deserialize an order reporting a position
+  /// intent.
+  #[test]
+  fn deserialize_order_with_position_intent() {
+    let json = br#"{
+    "id": "904837e3-3b76-47ec-b432-046db621571b",
+    "client_order_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "created_at": "2018-10-05T05:48:59Z",
+    "updated_at": "2018-10-05T05:48:59Z",
+    "submitted_at": "2018-10-05T05:48:59Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "failed_at": null,
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "15",
+    "filled_qty": "0",
+    "type": "market",
+    "order_class": "simple",
+    "side": "sell",
+    "position_intent": "sell_to_open",
+    "time_in_force": "day",
+    "limit_price": null,
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "accepted",
+    "extended_hours": false,
+    "legs": null
+}"#;
+
+    let order = from_json::<Order>(json).unwrap();
+    assert_eq!(order.position_intent, Some(PositionIntent::SellToOpen));
+    assert_eq!(order.position_intent.unwrap().side(), order.side);
+  }
+
+  /// Check that the position intent of an [`OrderReq`] is only
+  /// serialized if set.
+  #[test]
+  fn serialize_order_request_position_intent() {
+    let request = OrderReqInit::default().init("SPY", Side::Buy, Amount::quantity(1));
+    let json = from_json::<JsonValue>(&to_json(&request).unwrap()).unwrap();
+    assert_eq!(json.get("position_intent"), None);
+
+    let request = OrderReqInit {
+      position_intent: Some(PositionIntent::BuyToClose),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+    let json = to_json(&request).unwrap();
+    let value = from_json::<JsonValue>(&json).unwrap();
+    assert_eq!(value["position_intent"], "buy_to_close");
+    assert_eq!(from_json::<OrderReq>(&json).unwrap(), request);
   }
 
   /// Check that we can serialize and deserialize an [`OrderReq`].
@@ -985,7 +1424,7 @@ mod tests {
   fn serialize_deserialize_order_request() {
     let request = OrderReqInit {
       type_: Type::TrailingStop,
-      trail_price: Some(Num::from(50)),
+      trail_price: Some(Price::from(50)),
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
@@ -998,7 +1437,7 @@ mod tests {
   #[test]
   fn serialize_deserialize_change_request() {
     let request = ChangeReqInit {
-      quantity: Num::from(37),
+      quantity: Quantity::from(37),
       time_in_force: TimeInForce::UntilCanceled,
       trail: Some(Num::from(42)),
       ..Default::default()
@@ -1021,7 +1460,7 @@ mod tests {
         class: Class::default(),
         type_: Type::Limit,
         time_in_force: TimeInForce::default(),
-        limit_price: Some(Num::from(1)),
+        limit_price: Some(Price::from(1)),
         stop_price: None,
         trail_price: None,
         trail_percent: None,
@@ -1029,6 +1468,7 @@ mod tests {
         stop_loss: None,
         extended_hours,
         client_order_id: None,
+        position_intent: None,
       };
 
       let api_info = ApiInfo::from_env().unwrap();
@@ -1043,7 +1483,7 @@ mod tests {
       assert_eq!(order.type_, Type::Limit);
       assert_eq!(order.class, Class::default());
       assert_eq!(order.time_in_force, TimeInForce::Day);
-      assert_eq!(order.limit_price, Some(Num::from(1)));
+      assert_eq!(order.limit_price, Some(Price::from(1)));
       assert_eq!(order.stop_price, None);
       assert_eq!(order.extended_hours, extended_hours);
       Ok(())
@@ -1069,7 +1509,7 @@ mod tests {
   async fn submit_trailing_stop_price_order() {
     let request = OrderReqInit {
       type_: Type::TrailingStop,
-      trail_price: Some(Num::from(50)),
+      trail_price: Some(Price::from(50)),
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
@@ -1088,7 +1528,7 @@ mod tests {
     assert_eq!(order.limit_price, None);
     // We don't check the stop price here. It may be set to a value that
     // we can't know in advance.
-    assert_eq!(order.trail_price, Some(Num::from(50)));
+    assert_eq!(order.trail_price, Some(Price::from(50)));
     assert_eq!(order.trail_percent, None);
   }
 
@@ -1125,9 +1565,9 @@ mod tests {
     let request = OrderReqInit {
       class: Class::Bracket,
       type_: Type::Limit,
-      limit_price: Some(Num::from(2)),
-      take_profit: Some(TakeProfit::Limit(Num::from(3))),
-      stop_loss: Some(StopLoss::Stop(Num::from(1))),
+      limit_price: Some(Price::from(2)),
+      take_profit: Some(TakeProfit::Limit(Price::from(3))),
+      stop_loss: Some(StopLoss::Stop(Price::from(1))),
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
@@ -1148,7 +1588,7 @@ mod tests {
     assert_eq!(order.type_, Type::Limit);
     assert_eq!(order.class, Class::Bracket);
     assert_eq!(order.time_in_force, TimeInForce::Day);
-    assert_eq!(order.limit_price, Some(Num::from(2)));
+    assert_eq!(order.limit_price, Some(Price::from(2)));
     assert_eq!(order.stop_price, None);
     assert!(!order.extended_hours);
     assert_eq!(order.legs.len(), 2);
@@ -1161,8 +1601,8 @@ mod tests {
     let request = OrderReqInit {
       class: Class::OneTriggersOther,
       type_: Type::Limit,
-      limit_price: Some(Num::from(2)),
-      stop_loss: Some(StopLoss::Stop(Num::from(1))),
+      limit_price: Some(Price::from(2)),
+      stop_loss: Some(StopLoss::Stop(Price::from(1))),
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
@@ -1183,7 +1623,7 @@ mod tests {
     assert_eq!(order.type_, Type::Limit);
     assert_eq!(order.class, Class::OneTriggersOther);
     assert_eq!(order.time_in_force, TimeInForce::Day);
-    assert_eq!(order.limit_price, Some(Num::from(2)));
+    assert_eq!(order.limit_price, Some(Price::from(2)));
     assert_eq!(order.stop_price, None);
     assert!(!order.extended_hours);
     assert_eq!(order.legs.len(), 1);
@@ -1201,7 +1641,7 @@ mod tests {
         type_: Type::Limit,
         class: Class::Simple,
         time_in_force,
-        limit_price: Some(Num::from(1)),
+        limit_price: Some(Price::from(1)),
         ..Default::default()
       }
       .init("AAPL", Side::Buy, Amount::quantity(1));
@@ -1232,7 +1672,7 @@ mod tests {
 
     let request = OrderReqInit {
       type_: Type::Limit,
-      limit_price: Some(Num::from(1000)),
+      limit_price: Some(Price::from(1000)),
       ..Default::default()
     }
     .init("AAPL", Side::Buy, Amount::quantity(100_000));
@@ -1298,6 +1738,124 @@ mod tests {
     };
   }
 
+  /// Check that we can classify errors reported by `cancel`.
+  #[test]
+  fn classify_cancel_error() {
+    let err = CancelError::Delete(RequestError::Endpoint(DeleteError::NotFound(Err(
+      Vec::new(),
+    ))));
+    assert!(err.is_not_found());
+    assert!(!err.is_not_cancelable());
+
+    let err = CancelError::Delete(RequestError::Endpoint(DeleteError::NotCancelable(Err(
+      Vec::new(),
+    ))));
+    assert!(!err.is_not_found());
+    assert!(err.is_not_cancelable());
+  }
+
+  /// Check that we can cancel an order and wait for the cancellation
+  /// to be confirmed.
+  #[test(tokio::test)]
+  async fn cancel_order_confirmed() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let order = order_aapl(&client).await.unwrap();
+
+    let canceled = cancel(&client, &order.id, Some(Confirmation::default()))
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(canceled.id, order.id);
+    assert_eq!(canceled.status, Status::Canceled);
+
+    // A second attempt at canceling the order should fail, because it
+    // is no longer cancelable.
+    let err = cancel(&client, &order.id, None).await.unwrap_err();
+    assert!(err.is_not_cancelable(), "{:?}", err);
+  }
+
+  /// Check that we assign client order IDs only to order requests
+  /// lacking one.
+  #[test]
+  fn assign_client_order_id() {
+    let request = OrderReqInit::default().init("SPY", Side::Buy, Amount::quantity(1));
+    let first = ensure_client_order_id(request.clone());
+    let second = ensure_client_order_id(request);
+    assert!(first.client_order_id.is_some());
+    assert_ne!(first.client_order_id, second.client_order_id);
+
+    let request = OrderReqInit {
+      client_order_id: Some("my-id".to_string()),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+    let request = ensure_client_order_id(request);
+    assert_eq!(request.client_order_id, Some("my-id".to_string()));
+  }
+
+  /// Check that we correctly classify transient request errors.
+  #[test]
+  fn classify_transient_error() {
+    let err = RequestError::Endpoint(PostError::RateLimitExceeded(Err(Vec::new())));
+    assert!(is_transient!(&err, PostError));
+
+    let status = http::StatusCode::BAD_GATEWAY;
+    let err = RequestError::Endpoint(PostError::UnexpectedStatus(status, Err(Vec::new())));
+    assert!(is_transient!(&err, PostError));
+
+    let err = RequestError::Endpoint(PostError::InvalidInput(Err(Vec::new())));
+    assert!(!is_transient!(&err, PostError));
+
+    let err = RequestError::<PostError>::Io(std::io::ErrorKind::ConnectionReset.into());
+    assert!(is_transient!(&err, PostError));
+    assert!(is_ambiguous(&err));
+
+    let err = RequestError::Endpoint(PostError::RateLimitExceeded(Err(Vec::new())));
+    assert!(!is_ambiguous(&err));
+
+    let err = RequestError::Endpoint(PostError::InvalidInput(Err(Vec::new())));
+    assert!(!is_ambiguous(&err));
+    assert!(!may_be_duplicate(&err, false));
+    assert!(may_be_duplicate(&err, true));
+
+    let err = RequestError::Endpoint(PostError::NotPermitted(Err(Vec::new())));
+    assert!(!may_be_duplicate(&err, true));
+  }
+
+  /// Check that we can submit multiple orders at once.
+  #[test(tokio::test)]
+  async fn submit_multiple_orders() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let requests = ["AAPL", "MSFT", "SPY"]
+      .iter()
+      .map(|symbol| {
+        OrderReqInit {
+          type_: Type::Limit,
+          limit_price: Some(Price::from(1)),
+          ..Default::default()
+        }
+        .init(*symbol, Side::Buy, Amount::quantity(1))
+      })
+      .collect::<Vec<_>>();
+
+    let concurrency = NonZeroUsize::new(2).unwrap();
+    let submissions = submit_all(&client, requests, concurrency, Retry::default()).await;
+    assert_eq!(submissions.len(), 3);
+
+    for (submission, symbol) in submissions.iter().zip(["AAPL", "MSFT", "SPY"]) {
+      let order = submission.result.as_ref().unwrap();
+      let () = client.issue::<Delete>(&order.id).await.unwrap();
+
+      assert_eq!(order.symbol, symbol);
+      assert_eq!(
+        Some(&order.client_order_id),
+        submission.request.client_order_id.as_ref()
+      );
+    }
+  }
+
   /// Check that we can retrieve an order given its ID.
   #[test(tokio::test)]
   async fn retrieve_order_by_id() {
@@ -1361,7 +1919,7 @@ mod tests {
   async fn change_order() {
     let request = OrderReqInit {
       type_: Type::Limit,
-      limit_price: Some(Num::from(1)),
+      limit_price: Some(Price::from(1)),
       ..Default::default()
     }
     .init("AAPL", Side::Buy, Amount::quantity(1));
@@ -1371,9 +1929,9 @@ mod tests {
     let order = client.issue::<Post>(&request).await.unwrap();
 
     let request = ChangeReqInit {
-      quantity: Num::from(2),
+      quantity: Quantity::from(2),
       time_in_force: TimeInForce::UntilCanceled,
-      limit_price: Some(Num::from(2)),
+      limit_price: Some(Price::from(2)),
       ..Default::default()
     }
     .init();
@@ -1391,7 +1949,7 @@ mod tests {
       Ok(order) => {
         assert_eq!(order.amount, Amount::quantity(2));
         assert_eq!(order.time_in_force, TimeInForce::UntilCanceled);
-        assert_eq!(order.limit_price, Some(Num::from(2)));
+        assert_eq!(order.limit_price, Some(Price::from(2)));
         assert_eq!(order.stop_price, None);
       },
       Err(RequestError::Endpoint(PatchError::InvalidInput(..))) => {
@@ -1409,7 +1967,7 @@ mod tests {
   async fn change_trail_stop_order() {
     let request = OrderReqInit {
       type_: Type::TrailingStop,
-      trail_price: Some(Num::from(20)),
+      trail_price: Some(Price::from(20)),
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
@@ -1417,7 +1975,7 @@ mod tests {
     let api_info = ApiInfo::from_env().unwrap();
     let client = Client::new(api_info);
     let order = client.issue::<Post>(&request).await.unwrap();
-    assert_eq!(order.trail_price, Some(Num::from(20)));
+    assert_eq!(order.trail_price, Some(Price::from(20)));
 
     let request = ChangeReqInit {
       trail: Some(Num::from(30)),
@@ -1436,7 +1994,7 @@ mod tests {
 
     match result {
       Ok(order) => {
-        assert_eq!(order.trail_price, Some(Num::from(30)));
+        assert_eq!(order.trail_price, Some(Price::from(30)));
       },
       Err(RequestError::Endpoint(PatchError::InvalidInput(..))) => (),
       e => panic!("received unexpected error: {:?}", e),
@@ -1452,7 +2010,7 @@ mod tests {
 
     let request = OrderReqInit {
       type_: Type::Limit,
-      limit_price: Some(Num::from(1)),
+      limit_price: Some(Price::from(1)),
       client_order_id: Some(client_order_id.clone()),
       ..Default::default()
     }
@@ -1,13 +1,12 @@
 // Copyright (C) 2019-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use num_decimal::Num;
-
 use crate::api::v2::order;
 use crate::api::v2::order::Amount;
 use crate::api::v2::order::Side;
 use crate::api::v2::order::Type;
 use crate::Client;
+use crate::Price;
 use crate::RequestError;
 
 
@@ -22,7 +21,7 @@ where
 {
   let request = order::OrderReqInit {
     type_: Type::Limit,
-    limit_price: Some(Num::from(1)),
+    limit_price: Some(Price::from(1)),
     ..Default::default()
   }
   .init(symbol, Side::Buy, Amount::quantity(1));
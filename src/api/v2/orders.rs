@@ -90,13 +90,15 @@ Endpoint! {
 mod tests {
   use super::*;
 
+  #[cfg(feature = "streaming")]
   use futures::future::ok;
+  #[cfg(feature = "streaming")]
   use futures::pin_mut;
+  #[cfg(feature = "streaming")]
   use futures::StreamExt;
+  #[cfg(feature = "streaming")]
   use futures::TryStreamExt;
 
-  use num_decimal::Num;
-
   use serde_json::from_slice as from_json;
   use serde_json::to_vec as to_json;
   use serde_urlencoded::from_str as from_query;
@@ -107,9 +109,11 @@ mod tests {
   use crate::api::v2::order;
   use crate::api::v2::order_util::order_aapl;
   use crate::api::v2::order_util::order_stock;
+  #[cfg(feature = "streaming")]
   use crate::api::v2::updates;
   use crate::api_info::ApiInfo;
   use crate::Client;
+  use crate::Price;
 
 
   /// Make sure that we can serialize and deserialize an `OrdersReq`.
@@ -151,6 +155,7 @@ mod tests {
 
   /// Cancel an order and wait for the corresponding cancellation event
   /// to arrive.
+  #[cfg(feature = "streaming")]
   async fn cancel_order(client: &Client, id: order::Id) {
     let (stream, _subscription) = client.subscribe::<updates::OrderUpdates>().await.unwrap();
     pin_mut!(stream);
@@ -175,6 +180,15 @@ mod tests {
       .unwrap();
   }
 
+  /// Cancel an order.
+  ///
+  /// Without streaming support we have no way of waiting for the
+  /// cancellation to take effect.
+  #[cfg(not(feature = "streaming"))]
+  async fn cancel_order(client: &Client, id: order::Id) {
+    client.issue::<order::Delete>(&id).await.unwrap();
+  }
+
   /// Check that we can list existing orders.
   #[test(tokio::test)]
   async fn list_orders() {
@@ -223,8 +237,8 @@ mod tests {
     let request = order::OrderReqInit {
       class: order::Class::OneTriggersOther,
       type_: order::Type::Limit,
-      limit_price: Some(Num::from(2)),
-      take_profit: Some(order::TakeProfit::Limit(Num::from(3))),
+      limit_price: Some(Price::from(2)),
+      take_profit: Some(order::TakeProfit::Limit(Price::from(3))),
       ..Default::default()
     }
     .init("SPY", order::Side::Buy, order::Amount::quantity(1));
@@ -13,6 +13,9 @@ use serde::Serialize;
 use crate::api::v2::asset;
 use crate::api::v2::order;
 use crate::util::abs_num_from_str;
+use crate::Notional;
+use crate::Price;
+use crate::Quantity;
 use crate::Str;
 
 
@@ -58,37 +61,37 @@ pub struct Position {
   pub asset_class: asset::Class,
   /// The average entry price of the position.
   #[serde(rename = "avg_entry_price")]
-  pub average_entry_price: Num,
+  pub average_entry_price: Price,
   /// The number of shares.
   #[serde(rename = "qty", deserialize_with = "abs_num_from_str")]
-  pub quantity: Num,
+  pub quantity: Quantity,
   /// The side the position is on.
   #[serde(rename = "side")]
   pub side: Side,
   /// The total dollar amount of the position.
   #[serde(rename = "market_value")]
-  pub market_value: Option<Num>,
+  pub market_value: Option<Notional>,
   /// The total cost basis in dollar.
   #[serde(rename = "cost_basis")]
-  pub cost_basis: Num,
+  pub cost_basis: Notional,
   /// The total unrealized profit/loss in dollar.
   #[serde(rename = "unrealized_pl")]
-  pub unrealized_gain_total: Option<Num>,
+  pub unrealized_gain_total: Option<Notional>,
   /// The total unrealized profit/loss percent (as a factor of 1).
   #[serde(rename = "unrealized_plpc")]
   pub unrealized_gain_total_percent: Option<Num>,
   /// The unrealized profit/loss in dollar for the day.
   #[serde(rename = "unrealized_intraday_pl")]
-  pub unrealized_gain_today: Option<Num>,
+  pub unrealized_gain_today: Option<Notional>,
   /// The unrealized profit/loss percent for the day (as a factor of 1).
   #[serde(rename = "unrealized_intraday_plpc")]
   pub unrealized_gain_today_percent: Option<Num>,
   /// The current asset price per share.
   #[serde(rename = "current_price")]
-  pub current_price: Option<Num>,
+  pub current_price: Option<Price>,
   /// The last day's asset price per share.
   #[serde(rename = "lastday_price")]
-  pub last_day_price: Option<Num>,
+  pub last_day_price: Option<Price>,
   /// The percent change from last day price (as a factor of 1).
   #[serde(rename = "change_today")]
   pub change_today: Option<Num>,
@@ -189,17 +192,17 @@ mod tests {
     assert_eq!(pos.symbol, "AAPL");
     assert_eq!(pos.exchange, asset::Exchange::Nasdaq);
     assert_eq!(pos.asset_class, asset::Class::UsEquity);
-    assert_eq!(pos.average_entry_price, Num::from(100));
-    assert_eq!(pos.quantity, Num::from(5));
+    assert_eq!(pos.average_entry_price, Price::from(100));
+    assert_eq!(pos.quantity, Quantity::from(5));
     assert_eq!(pos.side, Side::Long);
-    assert_eq!(pos.market_value, Some(Num::from(600)));
-    assert_eq!(pos.cost_basis, Num::from(500));
-    assert_eq!(pos.unrealized_gain_total, Some(Num::from(100)));
+    assert_eq!(pos.market_value, Some(Notional::from(600)));
+    assert_eq!(pos.cost_basis, Notional::from(500));
+    assert_eq!(pos.unrealized_gain_total, Some(Notional::from(100)));
     assert_eq!(pos.unrealized_gain_total_percent, Some(Num::new(20, 100)));
-    assert_eq!(pos.unrealized_gain_today, Some(Num::from(10)));
+    assert_eq!(pos.unrealized_gain_today, Some(Notional::from(10)));
     assert_eq!(pos.unrealized_gain_today_percent, Some(Num::new(84, 10000)));
-    assert_eq!(pos.current_price, Some(Num::from(120)));
-    assert_eq!(pos.last_day_price, Some(Num::from(119)));
+    assert_eq!(pos.current_price, Some(Price::from(120)));
+    assert_eq!(pos.last_day_price, Some(Price::from(119)));
     assert_eq!(pos.change_today, Some(Num::new(84, 10000)));
   }
 
@@ -229,17 +232,17 @@ mod tests {
     assert_eq!(pos.symbol, "AAPL");
     assert_eq!(pos.exchange, asset::Exchange::Nasdaq);
     assert_eq!(pos.asset_class, asset::Class::UsEquity);
-    assert_eq!(pos.average_entry_price, Num::from(100));
-    assert_eq!(pos.quantity, Num::new(1, 2));
+    assert_eq!(pos.average_entry_price, Price::from(100));
+    assert_eq!(pos.quantity, Quantity::from(Num::new(1, 2)));
     assert_eq!(pos.side, Side::Long);
-    assert_eq!(pos.market_value, Some(Num::from(600)));
-    assert_eq!(pos.cost_basis, Num::from(500));
-    assert_eq!(pos.unrealized_gain_total, Some(Num::from(100)));
+    assert_eq!(pos.market_value, Some(Notional::from(600)));
+    assert_eq!(pos.cost_basis, Notional::from(500));
+    assert_eq!(pos.unrealized_gain_total, Some(Notional::from(100)));
     assert_eq!(pos.unrealized_gain_total_percent, Some(Num::new(20, 100)));
-    assert_eq!(pos.unrealized_gain_today, Some(Num::from(10)));
+    assert_eq!(pos.unrealized_gain_today, Some(Notional::from(10)));
     assert_eq!(pos.unrealized_gain_today_percent, Some(Num::new(84, 10000)));
-    assert_eq!(pos.current_price, Some(Num::from(120)));
-    assert_eq!(pos.last_day_price, Some(Num::from(119)));
+    assert_eq!(pos.current_price, Some(Price::from(120)));
+    assert_eq!(pos.last_day_price, Some(Price::from(119)));
     assert_eq!(pos.change_today, Some(Num::new(84, 10000)));
   }
 
@@ -267,7 +270,7 @@ mod tests {
 
     let pos = from_json::<Position>(response).unwrap();
     assert_eq!(pos.symbol, "XLK");
-    assert_eq!(pos.quantity, Num::from(24));
+    assert_eq!(pos.quantity, Quantity::from(24));
   }
 
   /// Check that we can retrieve an open position, if one exists.
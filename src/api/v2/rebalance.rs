@@ -0,0 +1,393 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+
+use num_decimal::Num;
+
+use thiserror::Error;
+
+use crate::api::v2::order::submit_all;
+use crate::api::v2::order::Amount;
+use crate::api::v2::order::OrderReq;
+use crate::api::v2::order::OrderReqInit;
+use crate::api::v2::order::Retry;
+use crate::api::v2::order::Side;
+use crate::api::v2::order::Submission;
+use crate::api::v2::position;
+use crate::api::v2::position::Position;
+use crate::util::abs;
+use crate::Client;
+use crate::Notional;
+use crate::Price;
+use crate::Quantity;
+
+
+/// The number of decimal places supported for fractional quantities.
+const FRACTIONAL_PRECISION: usize = 9;
+
+
+/// Constraints to honor when planning trades.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraints {
+  /// The minimum notional value of a trade. Trades of a smaller value
+  /// are omitted from the plan.
+  pub min_notional: Notional,
+  /// Whether fractional quantities can be traded. If not, quantities
+  /// are rounded towards zero to whole shares, except for positions
+  /// that are to be closed entirely.
+  pub fractional: bool,
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl Default for Constraints {
+  fn default() -> Self {
+    Self {
+      min_notional: Notional::from(1),
+      fractional: false,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// An error as reported by [`plan`].
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum PlanError {
+  /// No price was provided for a symbol that has a target weight or a
+  /// position.
+  #[error("no price available for {0}")]
+  MissingPrice(String),
+  /// The price provided for a symbol is not positive.
+  #[error("invalid price for {0}: {1}")]
+  InvalidPrice(String, Price),
+  /// The target weight for a symbol is negative.
+  #[error("invalid target weight for {0}: {1}")]
+  InvalidWeight(String, Num),
+}
+
+
+/// A trade as planned by [`plan`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trade {
+  /// The symbol to trade.
+  pub symbol: String,
+  /// The side of the trade.
+  pub side: Side,
+  /// The quantity to trade.
+  pub quantity: Quantity,
+  /// The estimated value of the trade, based on the price used for
+  /// planning.
+  pub notional: Notional,
+}
+
+impl Trade {
+  /// Create an order request for the trade, using the provided
+  /// `OrderReqInit` for all properties not determined by the trade
+  /// itself.
+  pub fn to_request(&self, init: OrderReqInit) -> OrderReq {
+    init.init(
+      self.symbol.clone(),
+      self.side,
+      Amount::quantity(self.quantity.clone()),
+    )
+  }
+}
+
+
+/// Truncate a `Num` to the given number of decimal places.
+fn trunc_with(num: &Num, precision: usize) -> Num {
+  let factor = Num::from(10u64.pow(precision as u32));
+  (num * &factor).trunc() / factor
+}
+
+
+/// Plan the trades necessary for moving from the provided positions
+/// to the given target weights.
+///
+/// Target weights are expressed as fractions of `equity`, i.e., a
+/// weight of `0.25` means that a quarter of the equity is to be held in
+/// the respective symbol. Weights do not have to add up to one, with
+/// the remainder being kept in cash. Positions in symbols without a
+/// target weight are closed. All values are calculated based on the
+/// provided prices.
+///
+/// The returned trades contain all sells ahead of all buys. Within
+/// each group trades are sorted by symbol.
+pub fn plan(
+  equity: &Notional,
+  targets: &HashMap<String, Num>,
+  positions: &[Position],
+  prices: &HashMap<String, Price>,
+  constraints: &Constraints,
+) -> Result<Vec<Trade>, PlanError> {
+  let current = positions
+    .iter()
+    .map(|position| {
+      let quantity = match position.side {
+        position::Side::Long => position.quantity.clone(),
+        position::Side::Short => -&position.quantity,
+      };
+      (position.symbol.as_str(), quantity)
+    })
+    .collect::<HashMap<_, _>>();
+
+  let symbols = targets
+    .keys()
+    .map(String::as_str)
+    .chain(current.keys().copied())
+    .collect::<BTreeSet<_>>();
+
+  let mut sells = Vec::new();
+  let mut buys = Vec::new();
+
+  for symbol in symbols {
+    let price = prices
+      .get(symbol)
+      .ok_or_else(|| PlanError::MissingPrice(symbol.to_string()))?;
+    if !price.is_positive() {
+      return Err(PlanError::InvalidPrice(symbol.to_string(), price.clone()))
+    }
+
+    let weight = targets.get(symbol).cloned().unwrap_or_default();
+    if weight.is_negative() {
+      return Err(PlanError::InvalidWeight(symbol.to_string(), weight))
+    }
+
+    let current = current.get(symbol).cloned().unwrap_or_default();
+    let target = &(equity * weight.clone()) / price;
+    let delta = if weight.is_zero() {
+      // Close the position entirely, irrespective of whether it is
+      // fractional.
+      -current
+    } else if constraints.fractional {
+      Quantity(trunc_with(&(target - current), FRACTIONAL_PRECISION))
+    } else {
+      Quantity((target - current).trunc())
+    };
+
+    if delta.is_zero() {
+      continue
+    }
+
+    let side = if delta.is_negative() {
+      Side::Sell
+    } else {
+      Side::Buy
+    };
+    let quantity = Quantity(abs(delta.into_inner()));
+    let notional = &quantity * price;
+    if notional < constraints.min_notional {
+      continue
+    }
+
+    let trade = Trade {
+      symbol: symbol.to_string(),
+      side,
+      quantity,
+      notional,
+    };
+
+    match side {
+      Side::Sell => sells.push(trade),
+      Side::Buy => buys.push(trade),
+    }
+  }
+
+  sells.extend(buys);
+  Ok(sells)
+}
+
+
+/// Submit the provided trades using [`submit_all`].
+///
+/// All sells are submitted before any of the buys, in order to not
+/// tie up buying power needlessly. Note, however, that this function
+/// does not wait for any of the sell orders to be filled.
+///
+/// The returned submissions are in the same order as the provided
+/// trades.
+pub async fn submit(
+  client: &Client,
+  trades: &[Trade],
+  init: OrderReqInit,
+  concurrency: NonZeroUsize,
+  retry: Retry,
+) -> Vec<Submission> {
+  let (sells, buys) = trades
+    .iter()
+    .enumerate()
+    .partition::<Vec<_>, _>(|(_, trade)| trade.side == Side::Sell);
+
+  let mut submissions = Vec::with_capacity(trades.len());
+  for group in [sells, buys] {
+    let requests = group
+      .iter()
+      .map(|(_, trade)| trade.to_request(init.clone()))
+      .collect::<Vec<_>>();
+    let submitted = submit_all(client, requests, concurrency, retry).await;
+    submissions.extend(group.into_iter().map(|(index, _)| index).zip(submitted));
+  }
+
+  submissions.sort_by_key(|(index, _)| *index);
+  submissions
+    .into_iter()
+    .map(|(_, submission)| submission)
+    .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use serde_json::from_str as from_json;
+
+  use test_log::test;
+
+
+  /// Create a position in the given symbol.
+  fn position(symbol: &str, quantity: &str, side: &str) -> Position {
+    let json = format!(
+      r#"{{
+        "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+        "symbol": "{}",
+        "exchange": "NASDAQ",
+        "asset_class": "us_equity",
+        "avg_entry_price": "100.0",
+        "qty": "{}",
+        "side": "{}",
+        "market_value": "0",
+        "cost_basis": "0",
+        "unrealized_pl": "0",
+        "unrealized_plpc": "0",
+        "unrealized_intraday_pl": "0",
+        "unrealized_intraday_plpc": "0",
+        "current_price": "100.0",
+        "lastday_price": "100.0",
+        "change_today": "0"
+      }}"#,
+      symbol, quantity, side
+    );
+    from_json::<Position>(&json).unwrap()
+  }
+
+  /// Create a map from the given symbol and value pairs.
+  fn map<T>(pairs: &[(&str, T)]) -> HashMap<String, T>
+  where
+    T: Clone,
+  {
+    pairs
+      .iter()
+      .map(|(symbol, value)| (symbol.to_string(), value.clone()))
+      .collect()
+  }
+
+  /// Create a trade.
+  fn trade(symbol: &str, side: Side, quantity: Num, price: i32) -> Trade {
+    Trade {
+      symbol: symbol.to_string(),
+      side,
+      notional: Notional(&quantity * Num::from(price)),
+      quantity: Quantity(quantity),
+    }
+  }
+
+
+  /// Check that we plan the expected trades for whole shares.
+  #[test]
+  fn plan_whole_shares() {
+    let equity = Notional::from(10000);
+    let targets = map(&[("AAPL", Num::new(1, 2)), ("MSFT", Num::new(3, 10))]);
+    let positions = [position("AAPL", "10", "long"), position("SPY", "5", "long")];
+    let prices = map(&[
+      ("AAPL", Price::from(150)),
+      ("MSFT", Price::from(300)),
+      ("SPY", Price::from(400)),
+    ]);
+
+    let trades = plan(
+      &equity,
+      &targets,
+      &positions,
+      &prices,
+      &Constraints::default(),
+    )
+    .unwrap();
+    // AAPL: 5000 / 150 = 33.3 shares, of which we hold 10.
+    // MSFT: 3000 / 300 = 10 shares.
+    let expected = vec![
+      trade("SPY", Side::Sell, Num::from(5), 400),
+      trade("AAPL", Side::Buy, Num::from(23), 150),
+      trade("MSFT", Side::Buy, Num::from(10), 300),
+    ];
+    assert_eq!(trades, expected);
+  }
+
+  /// Check that we honor fractional trading and the minimum notional.
+  #[test]
+  fn plan_fractional() {
+    let equity = Notional::from(1000);
+    let targets = map(&[("AAPL", Num::new(1, 2)), ("MSFT", Num::new(1, 2))]);
+    let positions = [
+      position("AAPL", "3", "long"),
+      position("MSFT", "1.665", "long"),
+      position("TSLA", "0.5", "long"),
+    ];
+    let prices = map(&[
+      ("AAPL", Price::from(200)),
+      ("MSFT", Price::from(300)),
+      ("TSLA", Price::from(1)),
+    ]);
+    let constraints = Constraints {
+      fractional: true,
+      min_notional: Notional::from(1),
+      ..Default::default()
+    };
+
+    let trades = plan(&equity, &targets, &positions, &prices, &constraints).unwrap();
+    // AAPL: 500 / 200 = 2.5 shares, of which we hold 3.
+    // MSFT: 500 / 300 = 1.6666... shares, of which we hold 1.665, but
+    //       topping up is below the minimum notional.
+    // TSLA: the position is to be closed, but is worth less than the
+    //       minimum notional.
+    let expected = vec![trade("AAPL", Side::Sell, Num::new(1, 2), 200)];
+    assert_eq!(trades, expected);
+  }
+
+  /// Check that we report errors for invalid inputs.
+  #[test]
+  fn plan_errors() {
+    let equity = Notional::from(1000);
+    let constraints = Constraints::default();
+    let positions = [position("SPY", "1", "short")];
+    let targets = map(&[("AAPL", Num::from(1))]);
+    let prices = map(&[("AAPL", Price::from(100))]);
+
+    let err = plan(&equity, &targets, &positions, &prices, &constraints).unwrap_err();
+    assert_eq!(err, PlanError::MissingPrice("SPY".to_string()));
+
+    let prices = map(&[("AAPL", Price::from(100)), ("SPY", Price::from(0))]);
+    let err = plan(&equity, &targets, &positions, &prices, &constraints).unwrap_err();
+    assert_eq!(
+      err,
+      PlanError::InvalidPrice("SPY".to_string(), Price::from(0))
+    );
+
+    let targets = map(&[("AAPL", Num::from(-1))]);
+    let prices = map(&[("AAPL", Price::from(100)), ("SPY", Price::from(10))]);
+    let err = plan(&equity, &targets, &positions, &prices, &constraints).unwrap_err();
+    assert_eq!(
+      err,
+      PlanError::InvalidWeight("AAPL".to_string(), Num::from(-1))
+    );
+
+    // A short position without a target is covered.
+    let targets = HashMap::new();
+    let trades = plan(&equity, &targets, &positions, &prices, &constraints).unwrap();
+    assert_eq!(trades, vec![trade("SPY", Side::Buy, Num::from(1), 10)]);
+  }
+}
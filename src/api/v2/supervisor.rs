@@ -0,0 +1,480 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+use std::mem::take;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::channel::mpsc::channel;
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::Receiver;
+use futures::channel::mpsc::Sender;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::channel::oneshot;
+use futures::future::select;
+use futures::future::Either;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use serde_json::Error as JsonError;
+
+use thiserror::Error;
+
+use tracing::debug;
+use tracing::warn;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+
+use crate::api::v2::order;
+use crate::api::v2::order::ChangeReq;
+use crate::api::v2::order::Order;
+use crate::api::v2::order::OrderReq;
+use crate::api::v2::position::Position;
+use crate::api::v2::positions;
+use crate::api::v2::updates::OrderUpdate;
+use crate::Client;
+use crate::RequestError;
+
+
+/// An error as reported by the order mutating methods of a
+/// [`Handle`].
+#[derive(Debug, Error)]
+pub enum MutationError<E> {
+  /// The supervisor is shutting down and no longer accepts order
+  /// mutations.
+  #[error("the supervisor is shutting down")]
+  ShuttingDown,
+  /// The request to the Alpaca API failed.
+  #[error("failed to issue request")]
+  Request(#[source] RequestError<E>),
+}
+
+impl<E> From<RequestError<E>> for MutationError<E> {
+  #[inline]
+  fn from(err: RequestError<E>) -> Self {
+    Self::Request(err)
+  }
+}
+
+
+/// An error encountered by a [`Supervisor`] while reconciling its
+/// state, as reported as part of the final [`State`].
+#[derive(Debug, Error)]
+pub enum SupervisorError {
+  /// The order with the given ID could not be retrieved while
+  /// reconciling state.
+  #[error("failed to retrieve order")]
+  Order(order::Id, #[source] RequestError<order::GetError>),
+  /// The positions could not be retrieved while reconciling state.
+  #[error("failed to retrieve positions")]
+  Positions(#[source] RequestError<positions::GetError>),
+}
+
+
+/// The reason for a [`Supervisor`] to have stopped.
+#[derive(Debug)]
+pub enum Reason {
+  /// Shutdown was requested via a [`Handle`].
+  Requested,
+  /// The order update stream ended.
+  StreamEnded,
+  /// The order update stream reported the contained error.
+  StreamError(WebSocketError),
+}
+
+
+/// The final state as reported by a [`Supervisor`] after shutdown.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct State {
+  /// The reason for the supervisor to have stopped.
+  pub reason: Reason,
+  /// All orders that were mutated (including canceled) through a
+  /// [`Handle`] or reported on the order update stream, ordered by
+  /// creation time. Orders that were not known to be in a terminal
+  /// state have been retrieved anew. Orders that failed to be
+  /// retrieved are contained in their last known state, if any.
+  pub orders: Vec<Order>,
+  /// The open positions, as retrieved after all order mutations
+  /// finished, or `None` if they could not be retrieved.
+  pub positions: Option<Vec<Position>>,
+  /// The errors encountered while reconciling state.
+  pub errors: Vec<SupervisorError>,
+}
+
+
+/// The state shared between a [`Supervisor`] and its handles.
+#[derive(Debug)]
+struct Shared {
+  /// The client used for all requests.
+  client: Client,
+  /// The sender used for requesting shutdown.
+  shutdown: UnboundedSender<()>,
+  /// A sender of which a clone is held by each in-flight order
+  /// mutation, or `None` once no new mutations are accepted.
+  in_flight: Mutex<Option<Sender<()>>>,
+  /// The senders of tasks waiting for shutdown, or `None` once no new
+  /// mutations are accepted.
+  waiters: Mutex<Option<Vec<oneshot::Sender<()>>>>,
+  /// The most recent state of all tracked orders, or `None` for orders
+  /// whose state is not known and has to be retrieved.
+  orders: Mutex<HashMap<order::Id, Option<Order>>>,
+}
+
+impl Shared {
+  /// Register a new order mutation, if such are still accepted.
+  ///
+  /// The mutation is considered in-flight for as long as the returned
+  /// object is alive.
+  fn begin(&self) -> Option<Sender<()>> {
+    self.in_flight.lock().unwrap().clone()
+  }
+
+  /// Stop accepting new order mutations.
+  fn close(&self) {
+    let _sender = self.in_flight.lock().unwrap().take();
+    // Dropping the senders wakes up all waiting tasks.
+    let _waiters = self.waiters.lock().unwrap().take();
+  }
+
+  /// Track the provided order, replacing any earlier state of it.
+  fn track(&self, order: Order) {
+    let _prev = self.orders.lock().unwrap().insert(order.id, Some(order));
+  }
+
+  /// Track the order with the provided ID, discarding any earlier
+  /// state of it.
+  fn track_id(&self, id: order::Id) {
+    let _prev = self.orders.lock().unwrap().insert(id, None);
+  }
+}
+
+
+/// A handle to a [`Supervisor`], used for mutating orders under its
+/// supervision and for requesting shutdown.
+#[derive(Clone, Debug)]
+pub struct Handle(Arc<Shared>);
+
+impl Handle {
+  /// Retrieve the client used by the supervisor.
+  #[inline]
+  pub fn client(&self) -> &Client {
+    &self.0.client
+  }
+
+  /// Request the supervisor to shut down.
+  ///
+  /// Once shutdown is requested no new order mutations are accepted.
+  /// Mutations already in-flight are not affected.
+  pub fn shutdown(&self) {
+    let () = self.0.close();
+    // The supervisor may already be gone, in which case there is
+    // nobody left to notify.
+    let _result = self.0.shutdown.unbounded_send(());
+  }
+
+  /// Check whether the supervisor is shutting down, i.e., no longer
+  /// accepts order mutations.
+  pub fn is_shutting_down(&self) -> bool {
+    self.0.in_flight.lock().unwrap().is_none()
+  }
+
+  /// Wait until the supervisor is shutting down, i.e., no longer
+  /// accepts order mutations.
+  ///
+  /// This is the case once shutdown was requested via any handle, or
+  /// once the order update stream ended.
+  pub async fn shutdown_requested(&self) {
+    let receiver = match &mut *self.0.waiters.lock().unwrap() {
+      Some(waiters) => {
+        // Clean up after tasks that stopped waiting.
+        let () = waiters.retain(|sender| !sender.is_canceled());
+        let (sender, receiver) = oneshot::channel();
+        let () = waiters.push(sender);
+        receiver
+      },
+      None => return,
+    };
+
+    // The sender is dropped without sending once shutting down.
+    let _result = receiver.await;
+  }
+
+  /// Submit an order.
+  pub async fn submit(&self, request: &OrderReq) -> Result<Order, MutationError<order::PostError>> {
+    let _guard = self.0.begin().ok_or(MutationError::ShuttingDown)?;
+    let order = self.0.client.issue::<order::Post>(request).await?;
+    let () = self.0.track(order.clone());
+    Ok(order)
+  }
+
+  /// Change an order.
+  pub async fn change(
+    &self,
+    id: order::Id,
+    request: ChangeReq,
+  ) -> Result<Order, MutationError<order::PatchError>> {
+    let _guard = self.0.begin().ok_or(MutationError::ShuttingDown)?;
+    let order = self.0.client.issue::<order::Patch>(&(id, request)).await?;
+    let () = self.0.track(order.clone());
+    Ok(order)
+  }
+
+  /// Request the cancellation of an order.
+  pub async fn cancel(&self, id: order::Id) -> Result<(), MutationError<order::DeleteError>> {
+    let _guard = self.0.begin().ok_or(MutationError::ShuttingDown)?;
+    let () = self.0.client.issue::<order::Delete>(&id).await?;
+    // The cancellation may take a while to take effect and so we
+    // retrieve the order's state during reconciliation.
+    let () = self.0.track_id(id);
+    Ok(())
+  }
+}
+
+
+/// A type owning a client, tracking the state of orders and
+/// coordinating a graceful shutdown.
+///
+/// Orders are mutated through [`Handle`] objects, which can be cloned
+/// freely and passed to other tasks. Order updates are consumed from
+/// the stream passed to [`run`][Supervisor::run]. Once shutdown is
+/// requested, or this stream ends, the supervisor stops accepting order
+/// mutations, waits for all in-flight ones to finish, and reconciles
+/// its state with the Alpaca API before reporting it.
+#[derive(Debug)]
+pub struct Supervisor {
+  /// The state shared with handles.
+  shared: Arc<Shared>,
+  /// The receiver for shutdown requests.
+  shutdown: UnboundedReceiver<()>,
+  /// The receiver used for waiting for in-flight order mutations.
+  in_flight: Receiver<()>,
+}
+
+impl Supervisor {
+  /// Create a new `Supervisor` using the provided client.
+  pub fn new(client: Client) -> Self {
+    let (shutdown_sender, shutdown) = unbounded();
+    let (in_flight_sender, in_flight) = channel(0);
+    let shared = Shared {
+      client,
+      shutdown: shutdown_sender,
+      in_flight: Mutex::new(Some(in_flight_sender)),
+      waiters: Mutex::new(Some(Vec::new())),
+      orders: Mutex::new(HashMap::new()),
+    };
+
+    Self {
+      shared: Arc::new(shared),
+      shutdown,
+      in_flight,
+    }
+  }
+
+  /// Retrieve a handle to the supervisor.
+  #[inline]
+  pub fn handle(&self) -> Handle {
+    Handle(Arc::clone(&self.shared))
+  }
+
+  /// Stop accepting new order mutations and wait for all in-flight
+  /// ones to finish.
+  async fn drain(&mut self) {
+    let () = self.shared.close();
+    // No sender is ever used for sending. Hence, the channel reports
+    // its end once all senders, held by in-flight mutations, have been
+    // dropped.
+    while self.in_flight.next().await.is_some() {}
+  }
+
+  /// Retrieve the current state of all tracked orders that may still
+  /// change as well as the open positions.
+  ///
+  /// Failures are recorded in the reported state, but do not prevent
+  /// the remaining state from being retrieved.
+  async fn reconcile(&self, reason: Reason) -> State {
+    let client = &self.shared.client;
+    let tracked = take(&mut *self.shared.orders.lock().unwrap());
+
+    let mut errors = Vec::new();
+    let mut orders = Vec::with_capacity(tracked.len());
+    for (id, order) in tracked {
+      match order {
+        Some(order) if order.status.is_terminal() => orders.push(order),
+        order => match client.issue::<order::Get>(&id).await {
+          Ok(order) => orders.push(order),
+          Err(err) => {
+            let () = orders.extend(order);
+            let () = errors.push(SupervisorError::Order(id, err));
+          },
+        },
+      }
+    }
+    orders.sort_by_key(|order| order.created_at);
+
+    let positions = match client.issue::<positions::Get>(&()).await {
+      Ok(positions) => Some(positions),
+      Err(err) => {
+        let () = errors.push(SupervisorError::Positions(err));
+        None
+      },
+    };
+
+    State {
+      reason,
+      orders,
+      positions,
+      errors,
+    }
+  }
+
+  /// Track the order updates reported by the provided stream until
+  /// shutdown is requested or the stream ends, and report the final
+  /// reconciled state.
+  pub async fn run<S>(mut self, mut updates: S) -> State
+  where
+    S: Stream<Item = Result<Result<OrderUpdate, JsonError>, WebSocketError>> + Unpin,
+  {
+    let reason = loop {
+      match select(updates.next(), self.shutdown.next()).await {
+        Either::Left((Some(Ok(Ok(update))), _)) => self.shared.track(update.order),
+        Either::Left((Some(Ok(Err(err))), _)) => {
+          warn!(error = debug(&err), "failed to decode order update");
+        },
+        Either::Left((Some(Err(err)), _)) => break Reason::StreamError(err),
+        Either::Left((None, _)) => break Reason::StreamEnded,
+        // We hold on to a sender ourselves and so the shutdown channel
+        // cannot be closed.
+        Either::Right(..) => break Reason::Requested,
+      }
+    };
+
+    debug!(reason = debug(&reason), "shutting down");
+    let () = self.drain().await;
+    self.reconcile(reason).await
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::time::Duration;
+
+  use futures::stream::empty;
+
+  use test_log::test;
+
+  use tokio::time::timeout;
+
+  use uuid::Uuid;
+
+  use crate::api::v2::order::Amount;
+  use crate::api::v2::order::OrderReqInit;
+  use crate::api::v2::order::Side;
+  use crate::api::API_BASE_URL;
+  use crate::api_info::ApiInfo;
+
+
+  /// Create a `Supervisor` using a client with invalid credentials.
+  fn supervisor() -> Supervisor {
+    let api_info = ApiInfo::from_parts(API_BASE_URL, "invalid", "invalid-too").unwrap();
+    Supervisor::new(Client::new(api_info))
+  }
+
+
+  /// Check that no order mutations are accepted once shutdown was
+  /// requested.
+  #[test(tokio::test)]
+  async fn reject_mutations_on_shutdown() {
+    let supervisor = supervisor();
+    let handle = supervisor.handle();
+    assert!(!handle.is_shutting_down());
+
+    let () = handle.shutdown();
+    assert!(handle.is_shutting_down());
+
+    let request = OrderReqInit::default().init("SPY", Side::Buy, Amount::quantity(1));
+    let result = handle.submit(&request).await;
+    assert!(
+      matches!(result, Err(MutationError::ShuttingDown)),
+      "{:?}",
+      result
+    );
+  }
+
+  /// Check that tasks waiting for shutdown are woken up once it is
+  /// requested.
+  #[test(tokio::test)]
+  async fn wait_for_shutdown_request() {
+    let supervisor = supervisor();
+    let handle = supervisor.handle();
+
+    let result = timeout(Duration::from_millis(100), handle.shutdown_requested()).await;
+    assert!(result.is_err());
+
+    let waiting = supervisor.handle();
+    let task = tokio::spawn(async move { waiting.shutdown_requested().await });
+    let () = handle.shutdown();
+    let () = timeout(Duration::from_secs(5), task)
+      .await
+      .unwrap()
+      .unwrap();
+
+    // Once shutting down we do not wait at all.
+    let () = timeout(Duration::from_secs(5), handle.shutdown_requested())
+      .await
+      .unwrap();
+  }
+
+  /// Check that we wait for in-flight order mutations when draining.
+  #[test(tokio::test)]
+  async fn wait_for_in_flight_mutations() {
+    let mut supervisor = supervisor();
+    let guard = supervisor.shared.begin().unwrap();
+
+    let result = timeout(Duration::from_millis(100), supervisor.drain()).await;
+    assert!(result.is_err());
+    assert!(supervisor.shared.begin().is_none());
+
+    drop(guard);
+    let () = timeout(Duration::from_secs(5), supervisor.drain())
+      .await
+      .unwrap();
+  }
+
+  /// Check that failures while reconciling are reported as part of the
+  /// final state.
+  #[test(tokio::test)]
+  async fn report_reconciliation_errors() {
+    let supervisor = supervisor();
+    let id = order::Id(Uuid::new_v4());
+    let () = supervisor.shared.track_id(id);
+
+    let state = supervisor.reconcile(Reason::Requested).await;
+    assert!(state.orders.is_empty());
+    assert!(state.positions.is_none());
+    assert_eq!(state.errors.len(), 2, "{:?}", state.errors);
+    assert!(matches!(state.errors[0], SupervisorError::Order(i, _) if i == id));
+    assert!(matches!(state.errors[1], SupervisorError::Positions(..)));
+  }
+
+  /// Check that a supervisor reports the final state once the order
+  /// update stream ends.
+  #[test(tokio::test)]
+  async fn report_state_on_stream_end() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let supervisor = Supervisor::new(Client::new(api_info));
+    let handle = supervisor.handle();
+
+    let state = supervisor.run(empty()).await;
+    assert!(matches!(state.reason, Reason::StreamEnded));
+    assert!(state.orders.is_empty());
+    assert!(state.positions.is_some());
+    assert!(state.errors.is_empty(), "{:?}", state.errors);
+    assert!(handle.is_shutting_down());
+  }
+}
@@ -2,13 +2,20 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
+use futures::ready;
 use futures::stream::Fuse;
+use futures::stream::FusedStream;
 use futures::stream::Map;
 use futures::stream::SplitSink;
 use futures::stream::SplitStream;
+use futures::task::Context;
+use futures::task::Poll;
 use futures::FutureExt as _;
 use futures::Sink;
 use futures::StreamExt as _;
@@ -21,6 +28,9 @@ use serde_json::to_string as to_json;
 use serde_json::Error as JsonError;
 
 use tokio::net::TcpStream;
+use tokio::time::sleep_until;
+use tokio::time::Instant;
+use tokio::time::Sleep;
 
 use tungstenite::MaybeTlsStream;
 use tungstenite::WebSocketStream;
@@ -366,47 +376,263 @@ impl Subscribable for OrderUpdates {
   type Stream = Fuse<MessageStream<SplitStream<Stream>, ParsedMessage>>;
 
   async fn connect(api_info: &Self::Input) -> Result<(Self::Stream, Self::Subscription), Error> {
-    fn map(result: Result<wrap::Message, WebSocketError>) -> ParsedMessage {
-      MessageResult::from(result.map(|message| match message {
-        wrap::Message::Text(string) => json_from_str::<OrderMessage>(&string),
-        wrap::Message::Binary(data) => json_from_slice::<OrderMessage>(&data),
-      }))
+    handshake(api_info, |_event| ()).await
+  }
+}
+
+
+/// Connect to the "trade_updates" event stream, reporting each step of
+/// the handshake to `on_event` as soon as it completed.
+async fn handshake<F>(
+  api_info: &ApiInfo,
+  mut on_event: F,
+) -> Result<
+  (
+    <OrderUpdates as Subscribable>::Stream,
+    <OrderUpdates as Subscribable>::Subscription,
+  ),
+  Error,
+>
+where
+  F: FnMut(Event) + Send,
+{
+  fn map(result: Result<wrap::Message, WebSocketError>) -> ParsedMessage {
+    MessageResult::from(result.map(|message| match message {
+      wrap::Message::Text(string) => json_from_str::<OrderMessage>(&string),
+      wrap::Message::Binary(data) => json_from_slice::<OrderMessage>(&data),
+    }))
+  }
+
+  let ApiInfo {
+    api_stream_url: url,
+    key_id,
+    secret,
+    ..
+  } = api_info;
+
+  let stream = connect(url).await?.map(map as MapFn);
+  let () = on_event(Event::Connected);
+  let (send, recv) = stream.split();
+  let (stream, subscription) = subscribe::subscribe(recv, send);
+  let mut stream = stream.fuse();
+
+  let mut subscription = Subscription(subscription);
+  let authenticate = subscription.authenticate(key_id, secret).boxed().fuse();
+  let () = subscribe::drive::<ParsedMessage, _, _>(authenticate, &mut stream)
+    .await
+    .map_err(|result| {
+      result
+        .map(|result| Error::Json(result.unwrap_err()))
+        .map_err(Error::WebSocket)
+        .unwrap_or_else(|err| err)
+    })???;
+  let () = on_event(Event::Authenticated);
+
+  let listen = subscription.listen().boxed().fuse();
+  let () = subscribe::drive::<ParsedMessage, _, _>(listen, &mut stream)
+    .await
+    .map_err(|result| {
+      result
+        .map(|result| Error::Json(result.unwrap_err()))
+        .map_err(Error::WebSocket)
+        .unwrap_or_else(|err| err)
+    })???;
+  let () = on_event(Event::Listening);
+
+  Ok((stream, subscription))
+}
+
+
+/// The reason for the disconnection of an order update stream.
+#[derive(Debug)]
+pub enum Disconnect {
+  /// The connection was closed, e.g., by the server.
+  Closed,
+  /// The connection failed with the contained error.
+  Error(WebSocketError),
+}
+
+
+/// An event as emitted by an [`OrderUpdateEvents`] stream.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum Event {
+  /// A websocket connection to the server was established.
+  Connected,
+  /// The connection was authenticated successfully.
+  Authenticated,
+  /// The server confirmed that it will send order updates.
+  Listening,
+  /// An order update was received, or failed to be decoded.
+  Update(Result<OrderUpdate, JsonError>),
+  /// No message was received for the configured idle timeout. See
+  /// [`Events::with_idle_timeout`].
+  Idle,
+  /// The connection was terminated. No events follow this one.
+  Disconnected(Disconnect),
+}
+
+
+/// A stream of [`Event`] objects, wrapping an order update stream.
+///
+/// Note that when configured with an idle timeout, an `Events` object
+/// relies on `tokio` timers and so it has to be polled from within a
+/// `tokio` runtime with the time driver enabled.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Events<S> {
+  /// The wrapped stream.
+  inner: S,
+  /// Events that occurred while the connection was established and
+  /// that have not yet been emitted.
+  connect: VecDeque<Event>,
+  /// The time without any message after which to report the stream as
+  /// idle.
+  idle: Option<Duration>,
+  /// The timer used for detecting an idle stream.
+  timer: Option<Pin<Box<Sleep>>>,
+  /// Whether the disconnection has been reported.
+  disconnected: bool,
+}
+
+impl<S> Events<S> {
+  /// Create a new `Events` object wrapping the provided stream, first
+  /// emitting the events that occurred while connecting.
+  fn new(inner: S, connect: VecDeque<Event>) -> Self {
+    Self {
+      inner,
+      connect,
+      idle: None,
+      timer: None,
+      disconnected: false,
     }
+  }
 
-    let ApiInfo {
-      api_stream_url: url,
-      key_id,
-      secret,
-      ..
-    } = api_info;
-
-    let stream = connect(url).await?.map(map as MapFn);
-    let (send, recv) = stream.split();
-    let (stream, subscription) = subscribe::subscribe(recv, send);
-    let mut stream = stream.fuse();
-
-    let mut subscription = Subscription(subscription);
-    let authenticate = subscription.authenticate(key_id, secret).boxed().fuse();
-    let () = subscribe::drive::<ParsedMessage, _, _>(authenticate, &mut stream)
-      .await
-      .map_err(|result| {
-        result
-          .map(|result| Error::Json(result.unwrap_err()))
-          .map_err(Error::WebSocket)
-          .unwrap_or_else(|err| err)
-      })???;
-
-    let listen = subscription.listen().boxed().fuse();
-    let () = subscribe::drive::<ParsedMessage, _, _>(listen, &mut stream)
-      .await
-      .map_err(|result| {
-        result
-          .map(|result| Error::Json(result.unwrap_err()))
-          .map_err(Error::WebSocket)
-          .unwrap_or_else(|err| err)
-      })???;
+  /// Report an [`Event::Idle`] whenever no message was received for
+  /// the provided duration.
+  ///
+  /// Alpaca only sends order updates when orders change and so an idle
+  /// stream does not necessarily indicate a problem. However, a
+  /// connection that stopped working without being closed is only
+  /// detectable this way.
+  pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+    self.idle = Some(timeout);
+    self
+  }
+}
+
+impl<S> futures::Stream for Events<S>
+where
+  S: futures::Stream<Item = Result<Result<OrderUpdate, JsonError>, WebSocketError>> + Unpin,
+{
+  type Item = Event;
+
+  fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+
+    if let Some(event) = this.connect.pop_front() {
+      return Poll::Ready(Some(event))
+    }
+
+    if this.disconnected {
+      return Poll::Ready(None)
+    }
+
+    let event = match this.inner.poll_next_unpin(ctx) {
+      Poll::Pending => {
+        let idle = match this.idle {
+          Some(idle) => idle,
+          None => return Poll::Pending,
+        };
+        let timer = this
+          .timer
+          .get_or_insert_with(|| Box::pin(sleep_until(Instant::now() + idle)));
+
+        let () = ready!(timer.poll_unpin(ctx));
+        // Keep reporting for as long as the stream stays idle.
+        timer.as_mut().reset(Instant::now() + idle);
+        Event::Idle
+      },
+      Poll::Ready(item) => {
+        if let (Some(idle), Some(timer)) = (this.idle, &mut this.timer) {
+          timer.as_mut().reset(Instant::now() + idle);
+        }
+
+        match item {
+          Some(Ok(result)) => Event::Update(result),
+          // We consider any websocket error fatal for the connection.
+          Some(Err(err)) => Event::Disconnected(Disconnect::Error(err)),
+          None => Event::Disconnected(Disconnect::Closed),
+        }
+      },
+    };
+
+    if let Event::Disconnected(..) = event {
+      this.disconnected = true;
+    }
+    Poll::Ready(Some(event))
+  }
+}
+
+impl<S> FusedStream for Events<S>
+where
+  S: futures::Stream<Item = Result<Result<OrderUpdate, JsonError>, WebSocketError>> + Unpin,
+{
+  #[inline]
+  fn is_terminated(&self) -> bool {
+    self.disconnected && self.connect.is_empty()
+  }
+}
+
+
+/// A type used for requesting a subscription to the "trade_updates"
+/// event stream, reporting connection lifecycle events in addition to
+/// order updates.
+///
+/// The events pertaining to the establishment of the connection are
+/// emitted first on the stream. Failures at this stage are reported by
+/// [`connect`][Subscribable::connect] directly, as is the case for
+/// [`OrderUpdates`]. Use [`OrderUpdateEvents::connect_with`] to learn
+/// about the steps that did complete before such a failure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderUpdateEvents {}
+
+impl OrderUpdateEvents {
+  /// Connect to the "trade_updates" event stream, additionally
+  /// reporting each step of the handshake to `on_event` as soon as it
+  /// completed.
+  pub async fn connect_with<F>(
+    api_info: &ApiInfo,
+    mut on_event: F,
+  ) -> Result<
+    (
+      <Self as Subscribable>::Stream,
+      <Self as Subscribable>::Subscription,
+    ),
+    Error,
+  >
+  where
+    F: FnMut(&Event) + Send,
+  {
+    let mut events = VecDeque::new();
+    let (stream, subscription) = handshake(api_info, |event| {
+      let () = on_event(&event);
+      let () = events.push_back(event);
+    })
+    .await?;
+    Ok((Events::new(stream, events), subscription))
+  }
+}
+
+#[async_trait]
+impl Subscribable for OrderUpdateEvents {
+  type Input = ApiInfo;
+  type Subscription = <OrderUpdates as Subscribable>::Subscription;
+  type Stream = Events<<OrderUpdates as Subscribable>::Stream>;
 
-    Ok((stream, subscription))
+  async fn connect(api_info: &Self::Input) -> Result<(Self::Stream, Self::Subscription), Error> {
+    Self::connect_with(api_info, |_event| ()).await
   }
 }
 
@@ -415,6 +641,7 @@ impl Subscribable for OrderUpdates {
 mod tests {
   use super::*;
 
+  use futures::channel::mpsc::unbounded;
   use futures::channel::oneshot::channel;
   use futures::future::ok;
   use futures::future::ready;
@@ -425,6 +652,8 @@ mod tests {
 
   use test_log::test;
 
+  use tokio::time::timeout;
+
   use websocket_util::test::WebSocketStream;
   use websocket_util::tungstenite::error::ProtocolError;
   use websocket_util::tungstenite::Message;
@@ -432,6 +661,7 @@ mod tests {
   use crate::api::v2::order;
   use crate::api::v2::order_util::order_aapl;
   use crate::api::API_BASE_URL;
+  use crate::websocket::test::mock_api_info;
   use crate::websocket::test::mock_stream;
   use crate::Client;
   use crate::Error;
@@ -693,6 +923,129 @@ mod tests {
       .unwrap();
   }
 
+  /// Check that connection lifecycle events are reported around order
+  /// updates.
+  #[test(tokio::test)]
+  async fn lifecycle_events() {
+    let (sender, receiver) = channel();
+
+    let test = |mut stream: WebSocketStream| {
+      async move {
+        // Authentication.
+        assert_eq!(
+          stream.next().await.unwrap()?,
+          Message::Text(AUTH_REQ.to_string()),
+        );
+        stream.send(Message::Text(AUTH_RESP.to_string())).await?;
+
+        // Subscription.
+        assert_eq!(
+          stream.next().await.unwrap()?,
+          Message::Text(STREAM_REQ.to_string()),
+        );
+        stream.send(Message::Text(STREAM_RESP.to_string())).await?;
+
+        let () = receiver.await.unwrap();
+
+        stream
+          .send(Message::Text("{ foobarbaz }".to_string()))
+          .await?;
+        stream.send(Message::Close(None)).await?;
+        Ok(())
+      }
+    };
+
+    let (mut stream, _subscription) = mock_stream::<OrderUpdateEvents, _, _>(test).await.unwrap();
+    let () = sender.send(()).unwrap();
+
+    assert!(matches!(stream.next().await, Some(Event::Connected)));
+    assert!(matches!(stream.next().await, Some(Event::Authenticated)));
+    assert!(matches!(stream.next().await, Some(Event::Listening)));
+
+    assert!(matches!(stream.next().await, Some(Event::Update(Err(..)))));
+    assert!(matches!(
+      stream.next().await,
+      Some(Event::Disconnected(Disconnect::Closed))
+    ));
+    assert!(stream.next().await.is_none());
+    assert!(stream.is_terminated());
+  }
+
+  /// Check that a failed authentication is reported after the
+  /// connection was established.
+  #[test(tokio::test)]
+  async fn lifecycle_events_on_failed_authentication() {
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(AUTH_REQ.to_string()),
+      );
+      let response =
+        r#"{"stream":"authorization","data":{"action":"authenticate","status":"unauthorized"}}"#;
+      stream.send(Message::Text(response.to_string())).await?;
+      // Wait for the client to hang up.
+      let _result = stream.next().await;
+      Ok(())
+    }
+
+    let api_info = mock_api_info(test).await;
+    let mut events = Vec::new();
+    let result =
+      OrderUpdateEvents::connect_with(&api_info, |event| events.push(format!("{:?}", event))).await;
+
+    match result {
+      Err(Error::Str(ref e)) if e == "authentication not successful" => (),
+      Err(e) => panic!("received unexpected error: {}", e),
+      Ok(..) => panic!("authentication succeeded unexpectedly"),
+    }
+    assert_eq!(events, vec!["Connected".to_string()]);
+  }
+
+  /// Check that a websocket error is reported as the reason for a
+  /// disconnection.
+  #[test(tokio::test)]
+  async fn disconnect_on_error() {
+    let items = vec![Err(WebSocketError::ConnectionClosed)];
+    let mut events = Events::new(futures::stream::iter(items), VecDeque::new());
+
+    assert!(matches!(
+      events.next().await,
+      Some(Event::Disconnected(Disconnect::Error(
+        WebSocketError::ConnectionClosed
+      )))
+    ));
+    assert!(events.next().await.is_none());
+  }
+
+  /// Check that an idle stream is reported as such, repeatedly.
+  #[test(tokio::test)]
+  async fn idle_events() {
+    let idle = Duration::from_millis(100);
+    let (sender, receiver) = unbounded();
+    let mut events = Events::new(receiver, VecDeque::new()).with_idle_timeout(idle);
+
+    let start = Instant::now();
+    for _ in 0..2 {
+      let event = timeout(Duration::from_secs(5), events.next())
+        .await
+        .unwrap();
+      assert!(matches!(event, Some(Event::Idle)));
+    }
+    assert!(start.elapsed() >= idle * 2);
+
+    let () = sender
+      .unbounded_send(Err(WebSocketError::AlreadyClosed))
+      .unwrap();
+    assert!(matches!(
+      events.next().await,
+      Some(Event::Disconnected(Disconnect::Error(
+        WebSocketError::AlreadyClosed
+      )))
+    ));
+    assert!(events.next().await.is_none());
+    assert!(events.is_terminated());
+  }
+
   /// Verify that ping websocket messages are responded to with pongs.
   #[test(tokio::test)]
   async fn ping_pong() {
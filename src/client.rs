@@ -13,6 +13,7 @@ use http::HeaderMap;
 use http::HeaderValue;
 use http::Request;
 use http::Response;
+use http::StatusCode;
 use http_endpoint::Endpoint;
 
 use hyper::body::to_bytes;
@@ -24,9 +25,14 @@ use hyper::Client as HttpClient;
 use hyper::Error as HyperError;
 use hyper_tls::HttpsConnector;
 
+use serde::de::DeserializeOwned;
+use serde_json::from_slice as json_from_slice;
+use serde_json::Error as JsonError;
+
 use tracing::debug;
 use tracing::field::debug;
 use tracing::field::DebugValue;
+#[cfg(feature = "streaming")]
 use tracing::instrument;
 use tracing::span;
 use tracing::trace;
@@ -39,7 +45,11 @@ use crate::api::HDR_KEY_ID;
 use crate::api::HDR_SECRET;
 use crate::api_info::ApiInfo;
 use crate::error::RequestError;
+use crate::pacing::Pacer;
+use crate::paginated::Paginated;
+#[cfg(feature = "streaming")]
 use crate::subscribable::Subscribable;
+#[cfg(feature = "streaming")]
 use crate::Error;
 
 
@@ -258,6 +268,25 @@ impl Client {
     &self,
     input: &R::Input,
   ) -> impl Future<Output = Result<R::Output, RequestError<R::Error>>> + '_
+  where
+    R: Endpoint,
+  {
+    let future = self.issue_raw::<R>(input);
+    async move {
+      let (status, body) = future.await?;
+      R::evaluate(status, &body).map_err(RequestError::Endpoint)
+    }
+  }
+
+  /// Create and issue a request and retrieve the HTTP status and the
+  /// raw body of the response, without evaluating either.
+  ///
+  /// This method can be used for accessing data not (yet) modeled by
+  /// the endpoint's output type. Also see [`Client::issue_as`].
+  pub fn issue_raw<R>(
+    &self,
+    input: &R::Input,
+  ) -> impl Future<Output = Result<(StatusCode, Bytes), RequestError<R::Error>>> + '_
   where
     R: Endpoint,
   {
@@ -274,9 +303,42 @@ impl Client {
     }
   }
 
+  /// Create and issue a request and decode the response into the
+  /// provided type instead of the endpoint's output type.
+  ///
+  /// Responses indicating an error are reported just as by
+  /// [`Client::issue`].
+  pub fn issue_as<R, T>(
+    &self,
+    input: &R::Input,
+  ) -> impl Future<Output = Result<T, RequestError<R::Error>>> + '_
+  where
+    R: Endpoint,
+    R::ConversionError: From<JsonError>,
+    T: DeserializeOwned,
+  {
+    let future = self.issue_raw::<R>(input);
+    async move {
+      let (status, body) = future.await?;
+      if !status.is_success() {
+        // We let the endpoint evaluate the response in order to report
+        // the same error that `issue` would.
+        let () = R::evaluate(status, &body)
+          .map(|_| ())
+          .map_err(RequestError::Endpoint)?;
+      }
+
+      json_from_slice::<T>(&body)
+        .map_err(|err| RequestError::Endpoint(R::Error::from(R::ConversionError::from(err))))
+    }
+  }
+
   /// Issue a request.
   #[allow(clippy::cognitive_complexity)]
-  async fn issue_<R>(&self, request: Request<Body>) -> Result<R::Output, RequestError<R::Error>>
+  async fn issue_<R>(
+    &self,
+    request: Request<Body>,
+  ) -> Result<(StatusCode, Bytes), RequestError<R::Error>>
   where
     R: Endpoint,
   {
@@ -289,13 +351,45 @@ impl Client {
     trace!(response = debug(&result));
 
     let bytes = Self::retrieve_body::<R::Error>(result).await?;
-    let body = bytes.as_ref();
-    match from_utf8(body) {
+    match from_utf8(&bytes) {
       Ok(s) => trace!(body = display(&s)),
       Err(b) => trace!(body = display(&b)),
     }
 
-    R::evaluate(status, body).map_err(RequestError::Endpoint)
+    Ok((status, bytes))
+  }
+
+  /// Issue requests against a paginated endpoint until all pages have
+  /// been retrieved, returning the items of all pages combined.
+  ///
+  /// If a [`Pacer`] is provided, it is consulted before each request
+  /// in order to stay within its requests-per-minute budget.
+  pub async fn issue_paginated<R>(
+    &self,
+    input: &R::Input,
+    mut pacer: Option<&mut Pacer>,
+  ) -> Result<Vec<R::Item>, RequestError<R::Error>>
+  where
+    R: Paginated,
+  {
+    let mut items = Vec::new();
+    let mut next = None;
+
+    loop {
+      if let Some(pacer) = pacer.as_deref_mut() {
+        pacer.pace().await;
+      }
+
+      let current = next.as_ref().unwrap_or(input);
+      let output = self.issue::<R>(current).await?;
+      let (page, input) = R::into_page(current, output);
+      items.extend(page);
+
+      match input {
+        Some(input) => next = Some(input),
+        None => break Ok(items),
+      }
+    }
   }
 
   /// Subscribe to the given subscribable in order to receive updates.
@@ -306,6 +400,7 @@ impl Client {
   ///   [`ApiInfo`] object to the call; if your [`Subscribable`]
   ///   requires a different input then invoke its `connect` method
   ///   yourself
+  #[cfg(feature = "streaming")]
   #[instrument(level = "debug", skip(self))]
   pub async fn subscribe<S>(&self) -> Result<(S::Stream, S::Subscription), Error>
   where
@@ -326,8 +421,6 @@ impl Client {
 mod tests {
   use super::*;
 
-  use http::StatusCode;
-
   use test_log::test;
 
   use crate::endpoint::ApiError;
@@ -363,4 +456,32 @@ mod tests {
       _ => panic!("Received unexpected error: {:?}", err),
     };
   }
+
+  /// Check that we can retrieve the raw response to a request.
+  #[test(tokio::test)]
+  async fn issue_raw_request() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::builder().max_idle_per_host(0).build(api_info);
+    let (status, body) = client.issue_raw::<GetNotFound>(&()).await.unwrap();
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let error = json_from_slice::<ApiError>(&body).unwrap();
+    assert_eq!(error.code, 40410000);
+  }
+
+  /// Check that `issue_as` reports errors the same way `issue` does.
+  #[test(tokio::test)]
+  async fn issue_as_unexpected_status_code() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::builder().max_idle_per_host(0).build(api_info);
+    let result = client.issue_as::<GetNotFound, ()>(&()).await;
+    let err = result.unwrap_err();
+
+    match err {
+      RequestError::Endpoint(GetNotFoundError::UnexpectedStatus(status, _)) => {
+        assert_eq!(status, StatusCode::NOT_FOUND);
+      },
+      _ => panic!("Received unexpected error: {:?}", err),
+    };
+  }
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 /// Definitions for the second version of the Alpaca Data API.
+#[cfg(feature = "data")]
 pub mod v2;
 
 /// The API base URL used for retrieving market data.
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use chrono::DateTime;
+use chrono::NaiveDate;
 use chrono::Utc;
 
 use num_decimal::Num;
@@ -13,6 +14,7 @@ use serde_urlencoded::to_string as to_query;
 use crate::data::v2::Feed;
 use crate::data::DATA_BASE_URL;
 use crate::util::vec_from_str;
+use crate::Paginated;
 use crate::Str;
 
 
@@ -79,6 +81,13 @@ pub struct BarsReq {
   /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
   #[serde(rename = "feed")]
   pub feed: Option<Feed>,
+  /// The date as of which to apply the symbol mapping, i.e., to which
+  /// symbol renames are considered.
+  ///
+  /// Defaults to the current day. This is relevant when retrieving
+  /// data for symbols that changed over time.
+  #[serde(rename = "asof", skip_serializing_if = "Option::is_none")]
+  pub asof: Option<NaiveDate>,
   /// If provided we will pass a page token to continue where we left off.
   #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
   pub page_token: Option<String>,
@@ -94,6 +103,8 @@ pub struct BarsReqInit {
   pub adjustment: Option<Adjustment>,
   /// See `BarsReq::feed`.
   pub feed: Option<Feed>,
+  /// See `BarsReq::asof`.
+  pub asof: Option<NaiveDate>,
   /// See `BarsReq::page_token`.
   pub page_token: Option<String>,
   #[doc(hidden)]
@@ -121,6 +132,7 @@ impl BarsReqInit {
       limit: self.limit,
       adjustment: self.adjustment,
       feed: self.feed,
+      asof: self.asof,
       page_token: self.page_token,
     }
   }
@@ -192,10 +204,27 @@ Endpoint! {
 }
 
 
+impl Paginated for Get {
+  type Item = Bar;
+
+  fn into_page(
+    input: &Self::Input,
+    output: Self::Output,
+  ) -> (Vec<Self::Item>, Option<Self::Input>) {
+    let next = output.next_page_token.map(|token| BarsReq {
+      page_token: Some(token),
+      ..input.clone()
+    });
+    (output.bars, next)
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use std::num::NonZeroU32;
   use std::str::FromStr as _;
 
   use http_endpoint::Endpoint;
@@ -206,6 +235,7 @@ mod tests {
 
   use crate::api_info::ApiInfo;
   use crate::Client;
+  use crate::Pacer;
   use crate::RequestError;
 
 
@@ -249,6 +279,21 @@ mod tests {
     assert!(res.next_page_token.is_some())
   }
 
+  /// Check that the `asof` parameter is only included in the query if
+  /// it was specified.
+  #[test]
+  fn serialize_asof() {
+    let start = DateTime::from_str("2022-06-01T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2022-06-10T00:00:00Z").unwrap();
+    let mut request = BarsReqInit::default().init("META", start, end, TimeFrame::OneDay);
+    let query = to_query(&request).unwrap();
+    assert!(!query.contains("asof"), "{}", query);
+
+    request.asof = NaiveDate::from_ymd_opt(2022, 6, 1);
+    let query = to_query(&request).unwrap();
+    assert!(query.contains("&asof=2022-06-01"), "{}", query);
+  }
+
   /// Check that we can decode a response containing no bars correctly.
   #[test(tokio::test)]
   async fn no_bars() {
@@ -436,4 +481,31 @@ mod tests {
       _ => panic!("Received unexpected error: {:?}", err),
     };
   }
+
+  /// Check that we can retrieve all pages of bars at once, with
+  /// requests being paced.
+  #[test(tokio::test)]
+  async fn download_bars_paced() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let start = DateTime::from_str("2021-11-05T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-11-12T00:00:00Z").unwrap();
+    let request = BarsReqInit {
+      limit: Some(2),
+      ..Default::default()
+    }
+    .init("AAPL", start, end, TimeFrame::OneDay);
+
+    let mut pacer = Pacer::new(NonZeroU32::new(600).unwrap());
+    let bars = client
+      .issue_paginated::<Get>(&request, Some(&mut pacer))
+      .await
+      .unwrap();
+
+    // There are five trading days in the week retrieved, which we get
+    // in three pages.
+    assert_eq!(bars.len(), 5);
+    assert!(bars.windows(2).all(|bars| bars[0].time < bars[1].time));
+  }
 }
@@ -0,0 +1,352 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::stream::FusedStream;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::FutureExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use serde_json::Error as JsonError;
+
+use tokio::time::sleep_until;
+use tokio::time::Instant;
+use tokio::time::Sleep;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+
+use super::stream::Data;
+use super::stream::Quote;
+
+
+/// The conflation state we maintain for a single symbol.
+#[derive(Debug)]
+struct Entry {
+  /// The time we last forwarded a quote for the symbol.
+  last: Instant,
+  /// The most recent quote received that has not yet been forwarded.
+  pending: Option<Quote>,
+}
+
+
+/// A wrapper around a market data stream that conflates quotes,
+/// delivering at most one quote per symbol per interval.
+///
+/// The first quote for a symbol is forwarded right away. Quotes for
+/// the same symbol that arrive within the configured interval are held
+/// back, with a more recent one replacing any held back earlier, and
+/// the latest is delivered once the interval has passed. All other
+/// items, including bars, trades, and errors, are passed through
+/// unchanged. Quotes still held back once the wrapped stream is
+/// exhausted are delivered before the end of the stream is reported.
+///
+/// Note that a `Conflate` object relies on `tokio` timers and so it has
+/// to be polled from within a `tokio` runtime with the time driver
+/// enabled.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Conflate<S> {
+  /// The wrapped stream.
+  inner: S,
+  /// The minimum interval between two quotes for the same symbol.
+  interval: Duration,
+  /// The conflation state, by symbol.
+  entries: HashMap<String, Entry>,
+  /// The symbols that have a quote pending, along with the time at
+  /// which it is due, earliest first.
+  due: BinaryHeap<Reverse<(Instant, String)>>,
+  /// A timer firing when the next pending quote is due.
+  timer: Option<Pin<Box<Sleep>>>,
+  /// Whether the wrapped stream has been exhausted.
+  exhausted: bool,
+}
+
+impl<S> Conflate<S> {
+  /// Create a new `Conflate` object wrapping the provided stream and
+  /// delivering at most one quote per symbol per `interval`.
+  pub fn new(inner: S, interval: Duration) -> Self {
+    Self {
+      inner,
+      interval,
+      entries: HashMap::new(),
+      due: BinaryHeap::new(),
+      timer: None,
+      exhausted: false,
+    }
+  }
+
+  /// Retrieve the pending quote with the earliest due time, provided
+  /// it is due at `now`. If `now` is `None` any pending quote is
+  /// considered due.
+  fn pop_due(&mut self, now: Option<Instant>) -> Option<Quote> {
+    let due = self.next_due()?;
+    if now.map(|now| now < due).unwrap_or(false) {
+      return None
+    }
+
+    // Both unwraps are fine, as we just peeked at the element and every
+    // symbol due has an entry.
+    let Reverse((_, symbol)) = self.due.pop().unwrap();
+    let entry = self.entries.get_mut(&symbol).unwrap();
+    entry.last = now.unwrap_or_else(Instant::now);
+    entry.pending.take()
+  }
+
+  /// Determine the time at which the next pending quote is due.
+  fn next_due(&self) -> Option<Instant> {
+    self.due.peek().map(|Reverse((due, _))| *due)
+  }
+
+  /// Handle a quote received from the wrapped stream, returning it if
+  /// it is to be forwarded right away.
+  fn conflate(&mut self, quote: Quote, now: Instant) -> Option<Quote> {
+    match self.entries.get_mut(quote.symbol.as_str()) {
+      Some(entry) if now < entry.last + self.interval => {
+        // A symbol that already has a quote pending is accounted for
+        // with its due time already.
+        if entry.pending.is_none() {
+          let due = entry.last + self.interval;
+          let () = self.due.push(Reverse((due, quote.symbol.clone())));
+        }
+        entry.pending = Some(quote);
+        None
+      },
+      Some(entry) => {
+        entry.last = now;
+        Some(quote)
+      },
+      None => {
+        let entry = Entry {
+          last: now,
+          pending: None,
+        };
+        let _prev = self.entries.insert(quote.symbol.clone(), entry);
+        Some(quote)
+      },
+    }
+  }
+}
+
+impl<S> Stream for Conflate<S>
+where
+  S: Stream<Item = Result<Result<Data, JsonError>, WebSocketError>> + Unpin,
+{
+  type Item = S::Item;
+
+  fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+
+    loop {
+      if this.exhausted {
+        // There is no point in holding back quotes any longer, as no
+        // more recent ones can arrive.
+        match this.pop_due(None) {
+          Some(quote) => break Poll::Ready(Some(Ok(Ok(Data::Quote(quote))))),
+          None => break Poll::Ready(None),
+        }
+      }
+
+      let now = Instant::now();
+      if let Some(quote) = this.pop_due(Some(now)) {
+        break Poll::Ready(Some(Ok(Ok(Data::Quote(quote)))))
+      }
+
+      match this.inner.poll_next_unpin(ctx) {
+        Poll::Pending => {
+          let due = match this.next_due() {
+            Some(due) => due,
+            None => break Poll::Pending,
+          };
+
+          let timer = match &mut this.timer {
+            Some(timer) => {
+              timer.as_mut().reset(due);
+              timer
+            },
+            None => this.timer.insert(Box::pin(sleep_until(due))),
+          };
+
+          if timer.poll_unpin(ctx).is_pending() {
+            break Poll::Pending
+          }
+          // The timer fired. Continue above by forwarding the quote
+          // that is now due.
+        },
+        Poll::Ready(None) => {
+          this.exhausted = true;
+        },
+        Poll::Ready(Some(Ok(Ok(Data::Quote(quote))))) => {
+          if let Some(quote) = this.conflate(quote, now) {
+            break Poll::Ready(Some(Ok(Ok(Data::Quote(quote)))))
+          }
+        },
+        Poll::Ready(Some(item)) => break Poll::Ready(Some(item)),
+      }
+    }
+  }
+}
+
+impl<S> FusedStream for Conflate<S>
+where
+  S: Stream<Item = Result<Result<Data, JsonError>, WebSocketError>> + Unpin,
+{
+  #[inline]
+  fn is_terminated(&self) -> bool {
+    self.exhausted && self.due.is_empty()
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use chrono::DateTime;
+  use chrono::Utc;
+
+  use futures::channel::mpsc::unbounded;
+  use futures::stream::iter;
+  use futures::TryStreamExt as _;
+
+  use num_decimal::Num;
+
+  use test_log::test;
+
+  use tokio::time::timeout;
+
+  use super::super::stream::Trade;
+
+
+  /// Create a quote for the given symbol with the given bid price.
+  fn quote(symbol: &str, bid_price: i32) -> Quote {
+    Quote {
+      symbol: symbol.to_string(),
+      bid_price: Num::from(bid_price),
+      bid_size: 1,
+      ask_price: Num::from(bid_price + 1),
+      ask_size: 1,
+      timestamp: DateTime::<Utc>::from_str("2022-01-03T14:30:00Z").unwrap(),
+    }
+  }
+
+  /// Extract symbol and bid price from a data item that is expected to
+  /// be a quote.
+  fn unwrap_quote(data: Data) -> (String, Num) {
+    match data {
+      Data::Quote(quote) => (quote.symbol, quote.bid_price),
+      data => panic!("encountered unexpected data: {:?}", data),
+    }
+  }
+
+
+  /// Check that quotes for the same symbol get conflated while other
+  /// data is passed through.
+  #[test(tokio::test)]
+  async fn conflate_quotes() {
+    let trade = Trade {
+      symbol: "SPY".to_string(),
+      trade_id: 42,
+      trade_price: Num::from(450),
+      trade_size: 10,
+      timestamp: DateTime::<Utc>::from_str("2022-01-03T14:30:00Z").unwrap(),
+    };
+    let data = vec![
+      Data::Quote(quote("SPY", 1)),
+      Data::Quote(quote("SPY", 2)),
+      Data::Trade(trade),
+      Data::Quote(quote("SPY", 3)),
+      Data::Quote(quote("AAPL", 10)),
+    ];
+    let stream = iter(data.into_iter().map(Ok).map(Ok));
+    let data = Conflate::new(stream, Duration::from_secs(3600))
+      .map_err(|_| ())
+      .and_then(|result| async { result.map_err(|_| ()) })
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+
+    assert_eq!(data.len(), 4);
+    let mut data = data.into_iter();
+    assert_eq!(
+      unwrap_quote(data.next().unwrap()),
+      ("SPY".to_string(), Num::from(1))
+    );
+    assert!(data.next().unwrap().is_trade());
+    assert_eq!(
+      unwrap_quote(data.next().unwrap()),
+      ("AAPL".to_string(), Num::from(10))
+    );
+    // The held back quote is flushed once the stream is exhausted,
+    // with the latest one winning.
+    assert_eq!(
+      unwrap_quote(data.next().unwrap()),
+      ("SPY".to_string(), Num::from(3))
+    );
+  }
+
+  /// Check that held back quotes are delivered in the order they
+  /// become due, not the order they were received in.
+  #[test]
+  fn deliver_in_due_order() {
+    let interval = Duration::from_secs(10);
+    let mut conflate = Conflate::new(iter(Vec::<Data>::new()), interval);
+    let start = Instant::now();
+    let at = |secs| start + Duration::from_secs(secs);
+
+    assert!(conflate.conflate(quote("AAPL", 1), at(0)).is_some());
+    assert!(conflate.conflate(quote("SPY", 1), at(5)).is_some());
+    assert!(conflate.conflate(quote("SPY", 2), at(6)).is_none());
+    assert!(conflate.conflate(quote("AAPL", 2), at(7)).is_none());
+    assert!(conflate.conflate(quote("AAPL", 3), at(8)).is_none());
+    assert_eq!(conflate.next_due(), Some(at(10)));
+
+    assert!(conflate.pop_due(Some(at(9))).is_none());
+    let quote = Data::Quote(conflate.pop_due(Some(at(10))).unwrap());
+    assert_eq!(unwrap_quote(quote), ("AAPL".to_string(), Num::from(3)));
+    assert!(conflate.pop_due(Some(at(14))).is_none());
+    let quote = Data::Quote(conflate.pop_due(Some(at(15))).unwrap());
+    assert_eq!(unwrap_quote(quote), ("SPY".to_string(), Num::from(2)));
+    assert_eq!(conflate.next_due(), None);
+  }
+
+  /// Check that a held back quote is delivered once the interval has
+  /// passed.
+  #[test(tokio::test)]
+  async fn deliver_after_interval() {
+    let interval = Duration::from_millis(200);
+    let (sender, receiver) = unbounded();
+    let mut stream = Conflate::new(receiver, interval);
+
+    let start = Instant::now();
+    for bid_price in 1..=3 {
+      let () = sender
+        .unbounded_send(Ok(Ok(Data::Quote(quote("SPY", bid_price)))))
+        .unwrap();
+    }
+
+    let data = stream.next().await.unwrap().unwrap().unwrap();
+    assert_eq!(unwrap_quote(data), ("SPY".to_string(), Num::from(1)));
+
+    let data = timeout(Duration::from_secs(5), stream.next())
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap()
+      .unwrap();
+    assert!(start.elapsed() >= interval);
+    assert_eq!(unwrap_quote(data), ("SPY".to_string(), Num::from(3)));
+
+    drop(sender);
+    assert!(stream.next().await.is_none());
+    assert!(stream.is_terminated());
+  }
+}
@@ -0,0 +1,262 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use futures::channel::mpsc::channel;
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::Receiver;
+use futures::channel::mpsc::Sender;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::pin_mut;
+use futures::SinkExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use serde_json::Error as JsonError;
+
+use tracing::debug;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+
+use super::stream::Data;
+use crate::Error;
+
+
+/// A channel created lazily by a [`Demux`] for a symbol that data was
+/// received for.
+#[derive(Debug)]
+pub struct NewSymbol {
+  /// The symbol the channel carries data for.
+  pub symbol: String,
+  /// The receiving end of the channel.
+  pub receiver: Receiver<Data>,
+}
+
+
+/// A type for routing the data of a market data stream into separate
+/// channels, one per symbol.
+///
+/// Channels can be requested upfront for individual symbols, by means
+/// of [`route`][Demux::route]. In addition, channels for symbols that
+/// data is received for but that have not been requested are created
+/// lazily and announced via the receiver returned by
+/// [`new_symbols`][Demux::new_symbols]. Without such a receiver, data
+/// for those symbols is discarded.
+///
+/// All channels are bounded and a full channel causes processing of
+/// the stream to wait until capacity becomes available, i.e., a slow
+/// consumer of one symbol's data holds up all others. Dropping the
+/// receiving end of a channel stops the routing of data for the
+/// respective symbol. Dropping the `Demux` closes all channels.
+#[derive(Debug)]
+pub struct Demux {
+  /// The capacity of the channels we create.
+  capacity: usize,
+  /// The channels by symbol. A value of `None` indicates a channel
+  /// whose receiving end was dropped.
+  channels: HashMap<String, Option<Sender<Data>>>,
+  /// The sender for announcing lazily created channels, if any.
+  announce: Option<UnboundedSender<NewSymbol>>,
+}
+
+impl Demux {
+  /// Create a new `Demux` object, creating channels with the given
+  /// capacity.
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      channels: HashMap::new(),
+      announce: None,
+    }
+  }
+
+  /// Create a channel for data pertaining to the given symbol.
+  ///
+  /// A channel previously created for the symbol is closed.
+  pub fn route<S>(&mut self, symbol: S) -> Receiver<Data>
+  where
+    S: Into<String>,
+  {
+    let (sender, receiver) = channel(self.capacity);
+    let _prev = self.channels.insert(symbol.into(), Some(sender));
+    receiver
+  }
+
+  /// Retrieve a receiver announcing the channels created lazily for
+  /// symbols that data is received for.
+  ///
+  /// Only a single such receiver is supported. Retrieving a new one
+  /// closes the previous one.
+  pub fn new_symbols(&mut self) -> UnboundedReceiver<NewSymbol> {
+    let (sender, receiver) = unbounded();
+    self.announce = Some(sender);
+    receiver
+  }
+
+  /// Retrieve the channel for the given symbol, creating and
+  /// announcing it if necessary and possible.
+  fn channel(&mut self, symbol: &str) -> Option<&mut Option<Sender<Data>>> {
+    if !self.channels.contains_key(symbol) {
+      let announce = self.announce.as_ref()?;
+      let (sender, receiver) = channel(self.capacity);
+      let new = NewSymbol {
+        symbol: symbol.to_string(),
+        receiver,
+      };
+
+      if announce.unbounded_send(new).is_err() {
+        debug!("receiver for new symbols has been dropped");
+        self.announce = None;
+        return None
+      }
+      let _prev = self.channels.insert(symbol.to_string(), Some(sender));
+    }
+    self.channels.get_mut(symbol)
+  }
+
+  /// Route the data of the provided market data stream until it is
+  /// exhausted or an error is encountered.
+  pub async fn run<S>(&mut self, stream: S) -> Result<(), Error>
+  where
+    S: Stream<Item = Result<Result<Data, JsonError>, WebSocketError>>,
+  {
+    pin_mut!(stream);
+
+    while let Some(result) = stream.next().await {
+      let data = match result {
+        Ok(Ok(data)) => data,
+        Ok(Err(err)) => return Err(Error::Json(err)),
+        Err(err) => return Err(Error::WebSocket(err)),
+      };
+
+      if let Some(channel) = self.channel(data.symbol()) {
+        if let Some(sender) = channel {
+          if sender.send(data).await.is_err() {
+            // The receiver got dropped. Stop routing data for this
+            // symbol.
+            *channel = None;
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use chrono::DateTime;
+  use chrono::Utc;
+
+  use futures::stream::iter;
+  use futures::FutureExt as _;
+
+  use num_decimal::Num;
+
+  use serde_json::from_str as json_from_str;
+
+  use test_log::test;
+
+  use super::super::stream::Quote;
+  use super::super::stream::Trade;
+
+
+  /// Create a quote for the given symbol.
+  fn quote(symbol: &str) -> Data {
+    Data::Quote(Quote {
+      symbol: symbol.to_string(),
+      bid_price: Num::from(1),
+      bid_size: 1,
+      ask_price: Num::from(2),
+      ask_size: 1,
+      timestamp: DateTime::<Utc>::from_str("2022-01-03T14:30:00Z").unwrap(),
+    })
+  }
+
+  /// Create a trade for the given symbol.
+  fn trade(symbol: &str) -> Data {
+    Data::Trade(Trade {
+      symbol: symbol.to_string(),
+      trade_id: 1,
+      trade_price: Num::from(1),
+      trade_size: 1,
+      timestamp: DateTime::<Utc>::from_str("2022-01-03T14:30:00Z").unwrap(),
+    })
+  }
+
+  /// Collect the symbols and kinds of all data items currently in a
+  /// channel.
+  fn drain(receiver: &mut Receiver<Data>) -> Vec<(String, bool)> {
+    let mut items = Vec::new();
+    while let Some(Some(data)) = receiver.next().now_or_never() {
+      items.push((data.symbol().to_string(), data.is_quote()));
+    }
+    items
+  }
+
+
+  /// Check that data is routed to requested as well as lazily created
+  /// channels.
+  #[test(tokio::test)]
+  async fn route_by_symbol() {
+    let data = vec![quote("SPY"), trade("AAPL"), trade("SPY"), quote("AAPL")];
+    let stream = iter(data.into_iter().map(Ok).map(Ok));
+
+    let mut demux = Demux::new(8);
+    let mut spy = demux.route("SPY");
+    let mut new_symbols = demux.new_symbols();
+    let () = demux.run(stream).await.unwrap();
+
+    assert_eq!(
+      drain(&mut spy),
+      vec![("SPY".to_string(), true), ("SPY".to_string(), false)]
+    );
+
+    let NewSymbol {
+      symbol,
+      mut receiver,
+    } = new_symbols.next().now_or_never().unwrap().unwrap();
+    assert_eq!(symbol, "AAPL");
+    assert_eq!(
+      drain(&mut receiver),
+      vec![("AAPL".to_string(), false), ("AAPL".to_string(), true)]
+    );
+    assert!(new_symbols.next().now_or_never().is_none());
+  }
+
+  /// Check that data for symbols without a channel as well as for
+  /// symbols whose receiver got dropped is discarded.
+  #[test(tokio::test)]
+  async fn discard_unrouted() {
+    let data = vec![quote("SPY"), quote("AAPL"), quote("SPY")];
+    let stream = iter(data.into_iter().map(Ok).map(Ok));
+
+    let mut demux = Demux::new(0);
+    let spy = demux.route("SPY");
+    drop(spy);
+
+    let () = demux.run(stream).await.unwrap();
+    assert_eq!(demux.channels.len(), 1);
+    assert!(demux.channels["SPY"].is_none());
+  }
+
+  /// Check that errors reported by the stream end processing.
+  #[test(tokio::test)]
+  async fn stream_error() {
+    let err = json_from_str::<Quote>("{}").unwrap_err();
+    let stream = iter(vec![Ok(Ok(quote("SPY"))), Ok(Err(err))]);
+
+    let mut demux = Demux::new(8);
+    let mut spy = demux.route("SPY");
+    let result = demux.run(stream).await;
+    assert!(matches!(result, Err(Error::Json(..))), "{:?}", result);
+    assert_eq!(drain(&mut spy).len(), 1);
+  }
+}
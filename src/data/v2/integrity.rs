@@ -0,0 +1,229 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeSet;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+
+#[cfg(feature = "trading")]
+use crate::api::v2::calendar::OpenClose;
+use crate::data::v2::bars::Bar;
+use crate::util::to_eastern;
+
+
+/// A market session that bars are expected for.
+///
+/// This trait is implemented for [`NaiveDate`], representing the date
+/// of a session, and, with the `trading` feature enabled, for the
+/// [`OpenClose`][crate::api::v2::calendar::OpenClose] objects making
+/// up the market calendar.
+pub trait Session {
+  /// Retrieve the date of the session, in US Eastern time.
+  fn date(&self) -> NaiveDate;
+}
+
+impl Session for NaiveDate {
+  #[inline]
+  fn date(&self) -> NaiveDate {
+    *self
+  }
+}
+
+#[cfg(feature = "trading")]
+impl Session for OpenClose {
+  #[inline]
+  fn date(&self) -> NaiveDate {
+    self.date
+  }
+}
+
+
+/// A bar with a time stamp not after that of the bar preceding it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutOfOrder {
+  /// The index of the bar in the checked series.
+  pub index: usize,
+  /// The time stamp of the bar.
+  pub time: DateTime<Utc>,
+  /// The time stamp of the preceding bar.
+  pub previous: DateTime<Utc>,
+}
+
+
+/// A report about the integrity of a series of bars, as created by
+/// [`check`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Report {
+  /// The dates of market sessions for which no bar is present.
+  pub missing_sessions: Vec<NaiveDate>,
+  /// The time stamps of bars reporting a volume of zero.
+  pub zero_volume: Vec<DateTime<Utc>>,
+  /// Bars that are not in strictly ascending order of their time
+  /// stamps, including duplicates.
+  pub out_of_order: Vec<OutOfOrder>,
+}
+
+impl Report {
+  /// Check whether the report is free of any findings.
+  #[inline]
+  pub fn is_clean(&self) -> bool {
+    self.missing_sessions.is_empty() && self.zero_volume.is_empty() && self.out_of_order.is_empty()
+  }
+}
+
+
+/// Check a series of bars, as retrieved via
+/// [`bars::Get`][crate::data::v2::bars::Get], against the market
+/// sessions (typically the market calendar) covering the same time
+/// span.
+///
+/// A session is considered missing if no bar falls onto its date (in
+/// US Eastern time, which is what the calendar is expressed in). Note
+/// that gaps within a session are not reported, as bars are only
+/// reported for time frames in which trades occurred.
+pub fn check<S>(bars: &[Bar], sessions: &[S]) -> Report
+where
+  S: Session,
+{
+  let mut report = Report::default();
+  let mut dates = BTreeSet::new();
+  let mut previous = None;
+
+  for (index, bar) in bars.iter().enumerate() {
+    let _inserted = dates.insert(to_eastern(&bar.time).naive_local().date());
+
+    if bar.volume == 0 {
+      report.zero_volume.push(bar.time);
+    }
+
+    if let Some(previous) = previous {
+      if bar.time <= previous {
+        report.out_of_order.push(OutOfOrder {
+          index,
+          time: bar.time,
+          previous,
+        });
+      }
+    }
+    previous = Some(bar.time);
+  }
+
+  report.missing_sessions = sessions
+    .iter()
+    .map(Session::date)
+    .filter(|date| !dates.contains(date))
+    .collect();
+
+  report
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "trading")]
+  use chrono::NaiveTime;
+
+  use serde_json::from_str as from_json;
+
+
+  /// Create a session on the given date.
+  fn session(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  /// Create a bar with the given time stamp and volume.
+  fn bar(time: &str, volume: usize) -> Bar {
+    let json = format!(
+      r#"{{"t":"{}","o":133.32,"h":133.74,"l":133.31,"c":133.5,"v":{}}}"#,
+      time, volume
+    );
+    from_json(&json).unwrap()
+  }
+
+
+  /// Check that a consistent series of daily bars is reported as
+  /// clean.
+  #[test]
+  fn clean_daily_bars() {
+    let calendar = [session(2021, 11, 5), session(2021, 11, 8)];
+    let bars = [
+      bar("2021-11-05T04:00:00Z", 100),
+      bar("2021-11-08T05:00:00Z", 200),
+    ];
+    let report = check(&bars, &calendar);
+    assert!(report.is_clean(), "{:?}", report);
+  }
+
+  /// Check that bars can be checked against the market calendar.
+  #[cfg(feature = "trading")]
+  #[test]
+  fn check_against_calendar() {
+    let calendar = [OpenClose {
+      date: session(2022, 1, 3),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+    }];
+    let bars = [bar("2022-01-04T14:30:00Z", 10)];
+    let report = check(&bars, &calendar);
+    assert_eq!(report.missing_sessions, vec![session(2022, 1, 3)]);
+  }
+
+  /// Check that we detect missing sessions.
+  #[test]
+  fn missing_sessions() {
+    let calendar = [
+      session(2022, 1, 3),
+      session(2022, 1, 4),
+      session(2022, 1, 5),
+    ];
+    // The last bar is on January 5th in UTC, but still on January 4th
+    // in US Eastern time.
+    let bars = [
+      bar("2022-01-03T14:30:00Z", 10),
+      bar("2022-01-05T00:59:00Z", 10),
+    ];
+    let report = check(&bars, &calendar);
+    assert_eq!(
+      report.missing_sessions,
+      vec![NaiveDate::from_ymd_opt(2022, 1, 5).unwrap()]
+    );
+    assert!(report.zero_volume.is_empty());
+    assert!(report.out_of_order.is_empty());
+  }
+
+  /// Check that we detect zero volume bars as well as bars that are
+  /// out of order.
+  #[test]
+  fn zero_volume_and_out_of_order() {
+    let calendar = [session(2022, 1, 3)];
+    let bars = [
+      bar("2022-01-03T14:31:00Z", 10),
+      bar("2022-01-03T14:30:00Z", 0),
+      bar("2022-01-03T14:32:00Z", 10),
+      bar("2022-01-03T14:32:00Z", 10),
+    ];
+    let report = check(&bars, &calendar);
+    assert!(report.missing_sessions.is_empty());
+    assert_eq!(report.zero_volume, vec![bars[1].time]);
+    assert_eq!(
+      report.out_of_order,
+      vec![
+        OutOfOrder {
+          index: 1,
+          time: bars[1].time,
+          previous: bars[0].time,
+        },
+        OutOfOrder {
+          index: 3,
+          time: bars[3].time,
+          previous: bars[2].time,
+        },
+      ]
+    );
+  }
+}
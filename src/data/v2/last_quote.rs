@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use chrono::DateTime;
+use chrono::Duration;
 use chrono::Utc;
 
 use num_decimal::Num;
@@ -11,8 +12,12 @@ use serde::Serialize;
 use serde_json::from_slice as from_json;
 use serde_urlencoded::to_string as to_query;
 
+use thiserror::Error;
+
 use crate::data::v2::Feed;
 use crate::data::DATA_BASE_URL;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -75,6 +80,63 @@ pub struct Quote {
   pub bid_size: u64,
 }
 
+impl Quote {
+  /// Retrieve the age of the quote at the given point in time.
+  #[inline]
+  pub fn age_at(&self, now: DateTime<Utc>) -> Duration {
+    now - self.time
+  }
+
+  /// Check whether the quote is older than `max_age` at the given point
+  /// in time.
+  #[inline]
+  pub fn is_stale_at(&self, max_age: Duration, now: DateTime<Utc>) -> bool {
+    self.age_at(now) > max_age
+  }
+
+  /// Check whether the quote is currently older than `max_age`.
+  #[inline]
+  pub fn is_stale(&self, max_age: Duration) -> bool {
+    self.is_stale_at(max_age, Utc::now())
+  }
+
+  /// Treat the quote as an error if it is older than `max_age` at the
+  /// given point in time.
+  pub fn ensure_fresh_at(self, max_age: Duration, now: DateTime<Utc>) -> Result<Self, StaleQuote> {
+    if self.is_stale_at(max_age, now) {
+      Err(StaleQuote {
+        time: self.time,
+        age: self.age_at(now),
+        max_age,
+      })
+    } else {
+      Ok(self)
+    }
+  }
+
+  /// Treat the quote as an error if it is currently older than
+  /// `max_age`.
+  ///
+  /// See [`get_fresh`] for retrieving a quote with this check applied.
+  #[inline]
+  pub fn ensure_fresh(self, max_age: Duration) -> Result<Self, StaleQuote> {
+    self.ensure_fresh_at(max_age, Utc::now())
+  }
+}
+
+
+/// An error indicating that a quote is older than permitted.
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+#[error("quote is stale: its age of {age} exceeds the maximum of {max_age}")]
+pub struct StaleQuote {
+  /// The time stamp of the stale quote.
+  pub time: DateTime<Utc>,
+  /// The age of the quote at the time of the check.
+  pub age: Duration,
+  /// The maximum age that was permitted.
+  pub max_age: Duration,
+}
+
 
 EndpointNoParse! {
   /// The representation of a GET request to the
@@ -126,12 +188,41 @@ EndpointNoParse! {
 }
 
 
+/// An error as reported by [`get_fresh`].
+#[derive(Debug, Error)]
+pub enum GetFreshError {
+  /// The request for the latest quote failed.
+  #[error("failed to retrieve latest quote")]
+  Request(#[source] RequestError<GetError>),
+  /// The quote retrieved is older than permitted.
+  #[error(transparent)]
+  Stale(#[from] StaleQuote),
+}
+
+
+/// Retrieve the latest quote, treating it as an error if it is older
+/// than `max_age`.
+///
+/// This function guards against acting on an outdated quote (e.g., one
+/// from before the market opened).
+pub async fn get_fresh(
+  client: &Client,
+  request: &LastQuoteReq,
+  max_age: Duration,
+) -> Result<Quote, GetFreshError> {
+  let quote = client
+    .issue::<Get>(request)
+    .await
+    .map_err(GetFreshError::Request)?;
+  let quote = quote.ensure_fresh(max_age)?;
+  Ok(quote)
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
-  use chrono::Duration;
-
   use test_log::test;
 
   use crate::api_info::ApiInfo;
@@ -167,6 +258,41 @@ mod tests {
     assert_eq!(quote.bid_size, 1);
   }
 
+  /// Check that we can detect stale quotes.
+  #[test]
+  fn stale_quote() {
+    let response = br#"{"t":"2021-02-06T13:35:08Z","ap":387.7,"as":1,"bp":387.67,"bs":1}"#;
+    let quote = from_json::<Quote>(response).unwrap();
+    let now = DateTime::parse_from_rfc3339("2021-02-06T13:35:38Z")
+      .unwrap()
+      .with_timezone(&Utc);
+
+    assert_eq!(quote.age_at(now), Duration::seconds(30));
+    assert!(!quote.is_stale_at(Duration::minutes(1), now));
+    assert!(quote.is_stale_at(Duration::seconds(10), now));
+    assert!(quote.is_stale(Duration::days(1)));
+
+    let err = quote.clone().ensure_fresh(Duration::days(1)).unwrap_err();
+    assert_eq!(err.time, quote.time);
+    assert_eq!(err.max_age, Duration::days(1));
+    assert!(err.age > Duration::days(1));
+
+    let err = quote
+      .clone()
+      .ensure_fresh_at(Duration::seconds(10), now)
+      .unwrap_err();
+    let expected = StaleQuote {
+      time: quote.time,
+      age: Duration::seconds(30),
+      max_age: Duration::seconds(10),
+    };
+    assert_eq!(err, expected);
+    assert_eq!(
+      quote.clone().ensure_fresh_at(Duration::minutes(1), now),
+      Ok(quote)
+    );
+  }
+
   /// Verify that we can retrieve the last quote for an asset.
   #[test(tokio::test)]
   async fn request_last_quote() {
@@ -181,6 +307,25 @@ mod tests {
     assert!(quote.time >= Utc::now() - Duration::weeks(2));
   }
 
+  /// Verify that we can retrieve the last quote for an asset, subject
+  /// to a maximum age.
+  #[test(tokio::test)]
+  async fn request_fresh_last_quote() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let req = LastQuoteReqInit::default().init("SPY");
+    let quote = get_fresh(&client, &req, Duration::weeks(2)).await.unwrap();
+    assert!(quote.time >= Utc::now() - Duration::weeks(2));
+
+    let result = get_fresh(&client, &req, Duration::zero()).await;
+    assert!(
+      matches!(result, Err(GetFreshError::Stale(..))),
+      "{:?}",
+      result
+    );
+  }
+
   /// Verify that we can specify the SIP feed as the data source to use.
   #[test(tokio::test)]
   async fn sip_feed() {
@@ -1,16 +1,25 @@
 // Copyright (C) 2021-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+#[cfg(feature = "streaming")]
+mod conflate;
+#[cfg(feature = "streaming")]
+mod demux;
 mod feed;
+#[cfg(feature = "streaming")]
 mod unfold;
 
 /// Definitions for retrieval of market data bars.
 pub mod bars;
+/// Functionality for checking the integrity of historic bars against
+/// the market calendar.
+pub mod integrity;
 /// Functionality for retrieval of the most recent quote.
 pub mod last_quote;
 /// Functionality for retrieving historic quotes.
 pub mod quotes;
 /// Definitions for real-time streaming of market data.
+#[cfg(feature = "streaming")]
 pub mod stream;
 
 pub use feed::Feed;
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use chrono::DateTime;
+use chrono::NaiveDate;
 use chrono::Utc;
 
 use serde::Deserialize;
@@ -11,6 +12,7 @@ use serde_urlencoded::to_string as to_query;
 use crate::data::v2::Feed;
 use crate::data::DATA_BASE_URL;
 use crate::util::vec_from_str;
+use crate::Paginated;
 use crate::Str;
 
 /// A quote as returned by the /v2/stocks/<symbol>/quotes endpoint.
@@ -40,6 +42,8 @@ pub struct QuotesReqInit {
   pub limit: Option<usize>,
   /// See `QuotesReq::feed`.
   pub feed: Option<Feed>,
+  /// See `QuotesReq::asof`.
+  pub asof: Option<NaiveDate>,
   /// See `QuotesReq::page_token`.
   pub page_token: Option<String>,
   #[doc(hidden)]
@@ -59,6 +63,7 @@ impl QuotesReqInit {
       end,
       limit: self.limit,
       feed: self.feed,
+      asof: self.asof,
       page_token: self.page_token,
     }
   }
@@ -87,6 +92,12 @@ pub struct QuotesReq {
   /// The data feed to use.
   #[serde(rename = "feed")]
   pub feed: Option<Feed>,
+  /// The date as of which to apply the symbol mapping, i.e., to which
+  /// symbol renames are considered.
+  ///
+  /// Defaults to the current day.
+  #[serde(rename = "asof", skip_serializing_if = "Option::is_none")]
+  pub asof: Option<NaiveDate>,
   /// Pagination token to continue from.
   #[serde(rename = "page_token")]
   pub page_token: Option<String>,
@@ -121,6 +132,22 @@ Endpoint! {
 }
 
 
+impl Paginated for Get {
+  type Item = Quote;
+
+  fn into_page(
+    input: &Self::Input,
+    output: Self::Output,
+  ) -> (Vec<Self::Item>, Option<Self::Input>) {
+    let next = output.next_page_token.map(|token| QuotesReq {
+      page_token: Some(token),
+      ..input.clone()
+    });
+    (output.quotes, next)
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
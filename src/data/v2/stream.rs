@@ -56,6 +56,10 @@ use crate::ApiInfo;
 use crate::Error;
 use crate::Str;
 
+pub use super::conflate::Conflate;
+pub use super::demux::Demux;
+pub use super::demux::NewSymbol;
+
 
 type UserMessage = <ParsedMessage as subscribe::Message>::UserMessage;
 
@@ -305,6 +309,16 @@ pub enum Data {
 }
 
 impl Data {
+  /// Retrieve the symbol the data item pertains to.
+  #[inline]
+  pub fn symbol(&self) -> &str {
+    match self {
+      Self::Bar(bar) => &bar.symbol,
+      Self::Quote(quote) => &quote.symbol,
+      Self::Trade(trade) => &trade.symbol,
+    }
+  }
+
   /// Check whether this object is of the `Bar` variant.
   #[inline]
   pub fn is_bar(&self) -> bool {
@@ -14,6 +14,7 @@ use hyper::Error as HyperError;
 use serde_json::Error as JsonError;
 use thiserror::Error;
 use url::ParseError;
+#[cfg(feature = "streaming")]
 use websocket_util::tungstenite::Error as WebSocketError;
 
 use crate::Str;
@@ -88,6 +89,7 @@ pub enum Error {
     ParseError,
   ),
   /// A websocket error.
+  #[cfg(feature = "streaming")]
   #[error("encountered a websocket related error")]
   WebSocket(
     #[from]
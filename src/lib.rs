@@ -46,6 +46,12 @@
   where_clauses_object_safety,
   while_true
 )]
+// Without any of the API features enabled, most of the crate's
+// machinery is unused.
+#![cfg_attr(
+  not(any(feature = "data", feature = "trading")),
+  allow(dead_code, unreachable_pub, unused_imports, unused_macros)
+)]
 
 //! A crate for interacting with the Alpaca API.
 
@@ -65,8 +71,13 @@ pub mod data;
 mod api_info;
 mod client;
 mod error;
+mod money;
+mod pacing;
+mod paginated;
+#[cfg(feature = "streaming")]
 mod subscribable;
 mod util;
+#[cfg(feature = "streaming")]
 mod websocket;
 
 use std::borrow::Cow;
@@ -76,6 +87,13 @@ pub use crate::client::Client;
 pub use crate::endpoint::ApiError;
 pub use crate::error::Error;
 pub use crate::error::RequestError;
+pub use crate::money::CurrencyDisplay;
+pub use crate::money::Notional;
+pub use crate::money::Price;
+pub use crate::money::Quantity;
+pub use crate::pacing::Pacer;
+pub use crate::paginated::Paginated;
+#[cfg(feature = "streaming")]
 pub use crate::subscribable::Subscribable;
 
 type Str = Cow<'static, str>;
@@ -0,0 +1,333 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Deref;
+use std::ops::Div;
+use std::ops::Mul;
+use std::ops::Neg;
+use std::ops::Sub;
+use std::ops::SubAssign;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+
+/// The minimum number of decimal places used when displaying a
+/// monetary value along with its currency.
+const CURRENCY_PRECISION: usize = 2;
+
+
+/// A macro for defining a newtype wrapper around a `Num`, along with
+/// the operations that do not change the meaning of the value (i.e.,
+/// addition and subtraction of two values of the same type as well as
+/// scaling by a unit-less factor).
+macro_rules! define_num_type {
+  ( $(#[$docs:meta])* $name:ident ) => {
+    $(#[$docs])*
+    #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+    #[serde(transparent)]
+    pub struct $name(pub Num);
+
+    impl $name {
+      /// Convert the object into the wrapped `Num`.
+      #[inline]
+      pub fn into_inner(self) -> Num {
+        self.0
+      }
+    }
+
+    impl Deref for $name {
+      type Target = Num;
+
+      #[inline]
+      fn deref(&self) -> &Self::Target {
+        &self.0
+      }
+    }
+
+    impl<T> From<T> for $name
+    where
+      Num: From<T>,
+    {
+      #[inline]
+      fn from(value: T) -> Self {
+        Self(Num::from(value))
+      }
+    }
+
+    impl Display for $name {
+      #[inline]
+      fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, fmt)
+      }
+    }
+
+    impl Neg for $name {
+      type Output = Self;
+
+      #[inline]
+      fn neg(self) -> Self::Output {
+        Self(-self.0)
+      }
+    }
+
+    impl Neg for &$name {
+      type Output = $name;
+
+      #[inline]
+      fn neg(self) -> Self::Output {
+        $name(-&self.0)
+      }
+    }
+
+    impl_op!(impl Add, add, $name, $name => $name);
+    impl_op!(impl Sub, sub, $name, $name => $name);
+    impl_op!(impl Mul, mul, $name, Num => $name);
+    impl_op!(impl Div, div, $name, Num => $name);
+
+    impl AddAssign for $name {
+      #[inline]
+      fn add_assign(&mut self, other: Self) {
+        self.0 += other.0
+      }
+    }
+
+    impl SubAssign for $name {
+      #[inline]
+      fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0
+      }
+    }
+  };
+}
+
+
+/// Retrieve the `Num` contained in a value that is either one of our
+/// newtypes or a `Num` itself.
+trait AsNum {
+  fn as_num(&self) -> &Num;
+}
+
+impl AsNum for Num {
+  #[inline]
+  fn as_num(&self) -> &Num {
+    self
+  }
+}
+
+
+/// A macro for implementing a binary operation between two operand
+/// types, with all combinations of owned and borrowed operands.
+macro_rules! impl_op {
+  (impl $imp:ident, $method:ident, $lhs:ty, $rhs:ty => $out:ident) => {
+    impl $imp<$rhs> for $lhs {
+      type Output = $out;
+
+      #[inline]
+      fn $method(self, other: $rhs) -> Self::Output {
+        $out($imp::$method(self.as_num(), other.as_num()))
+      }
+    }
+
+    impl $imp<&$rhs> for $lhs {
+      type Output = $out;
+
+      #[inline]
+      fn $method(self, other: &$rhs) -> Self::Output {
+        $out($imp::$method(self.as_num(), other.as_num()))
+      }
+    }
+
+    impl $imp<$rhs> for &$lhs {
+      type Output = $out;
+
+      #[inline]
+      fn $method(self, other: $rhs) -> Self::Output {
+        $out($imp::$method(self.as_num(), other.as_num()))
+      }
+    }
+
+    impl $imp<&$rhs> for &$lhs {
+      type Output = $out;
+
+      #[inline]
+      fn $method(self, other: &$rhs) -> Self::Output {
+        $out($imp::$method(self.as_num(), other.as_num()))
+      }
+    }
+  };
+}
+
+
+define_num_type! {
+  /// A price per unit (e.g., per share) of an asset, in the currency of
+  /// the account.
+  Price
+}
+
+define_num_type! {
+  /// A monetary value (e.g., the total value of a position or the cash
+  /// balance of an account), in the currency of the account.
+  Notional
+}
+
+define_num_type! {
+  /// A number of units (e.g., shares) of an asset. This may be a
+  /// fractional value.
+  Quantity
+}
+
+impl AsNum for Price {
+  #[inline]
+  fn as_num(&self) -> &Num {
+    &self.0
+  }
+}
+
+impl AsNum for Notional {
+  #[inline]
+  fn as_num(&self) -> &Num {
+    &self.0
+  }
+}
+
+impl AsNum for Quantity {
+  #[inline]
+  fn as_num(&self) -> &Num {
+    &self.0
+  }
+}
+
+// The operations changing the kind of value at hand. All other
+// operations between the different types are deliberately not
+// provided.
+impl_op!(impl Mul, mul, Price, Quantity => Notional);
+impl_op!(impl Mul, mul, Quantity, Price => Notional);
+impl_op!(impl Div, div, Notional, Price => Quantity);
+impl_op!(impl Div, div, Notional, Quantity => Price);
+
+
+/// A type for displaying a monetary value along with its currency.
+#[derive(Debug)]
+pub struct CurrencyDisplay<'n, 'c> {
+  /// The value to display.
+  num: &'n Num,
+  /// The currency to display the value in.
+  currency: &'c str,
+}
+
+impl<'n, 'c> Display for CurrencyDisplay<'n, 'c> {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    let mut display = self.num.display();
+    let _ = display.min_precision(CURRENCY_PRECISION);
+
+    match fmt.precision() {
+      Some(precision) => write!(fmt, "{} {:.*}", self.currency, precision, display),
+      None => write!(fmt, "{} {}", self.currency, display),
+    }
+  }
+}
+
+impl Price {
+  /// Retrieve a display adapter for displaying the price in the
+  /// provided currency, e.g., "USD 13.50".
+  ///
+  /// At least two decimal places are always printed. A precision
+  /// specified as part of the format string limits the number of
+  /// decimal places.
+  #[inline]
+  pub fn display_in<'c>(&self, currency: &'c str) -> CurrencyDisplay<'_, 'c> {
+    CurrencyDisplay {
+      num: &self.0,
+      currency,
+    }
+  }
+}
+
+impl Notional {
+  /// Retrieve a display adapter for displaying the value in the
+  /// provided currency, e.g., "USD 1000.00".
+  ///
+  /// See [`Price::display_in`] for details.
+  #[inline]
+  pub fn display_in<'c>(&self, currency: &'c str) -> CurrencyDisplay<'_, 'c> {
+    CurrencyDisplay {
+      num: &self.0,
+      currency,
+    }
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use serde_json::from_str as from_json;
+  use serde_json::to_string as to_json;
+
+
+  /// Check that we can perform arithmetic on values of the same type.
+  #[test]
+  fn same_type_arithmetic() {
+    let price = Price::from(10) + Price::from(Num::new(1, 2));
+    assert_eq!(price, Price::from(Num::new(21, 2)));
+    assert_eq!(&price - &Price::from(1), Price::from(Num::new(19, 2)));
+    assert_eq!(price * Num::from(2), Price::from(21));
+
+    let mut quantity = Quantity::from(3);
+    quantity += Quantity::from(2);
+    quantity -= Quantity::from(1);
+    assert_eq!(quantity, Quantity::from(4));
+    assert_eq!(-quantity, Quantity::from(-4));
+  }
+
+  /// Check that the operations converting between value types work as
+  /// expected.
+  #[test]
+  fn cross_type_arithmetic() {
+    let price = Price::from(25);
+    let quantity = Quantity::from(4);
+    let notional = &price * &quantity;
+    assert_eq!(notional, Notional::from(100));
+    assert_eq!(&quantity * &price, notional);
+    assert_eq!(&notional / &price, quantity);
+    assert_eq!(&notional / &quantity, price);
+  }
+
+  /// Make sure that the newtypes serialize and deserialize just like a
+  /// plain `Num`.
+  #[test]
+  fn serialize_deserialize() {
+    let price = Price::from(Num::new(2775, 100));
+    let json = to_json(&price).unwrap();
+    assert_eq!(json, r#""27.75""#);
+    assert_eq!(from_json::<Price>(&json).unwrap(), price);
+    assert_eq!(
+      from_json::<Notional>("42.1").unwrap(),
+      Notional::from(Num::new(421, 10))
+    );
+  }
+
+  /// Check that we can display values along with a currency.
+  #[test]
+  fn display_in_currency() {
+    let price = Price::from(Num::new(27, 2));
+    assert_eq!(price.to_string(), "13.5");
+    assert_eq!(price.display_in("USD").to_string(), "USD 13.50");
+
+    let notional = Notional::from(Num::new(1, 3));
+    assert_eq!(format!("{:.3}", notional.display_in("EUR")), "EUR 0.333");
+    assert_eq!(
+      Notional::from(-5).display_in("USD").to_string(),
+      "USD -5.00"
+    );
+  }
+}
@@ -0,0 +1,223 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use tokio::time::sleep;
+use tokio::time::Instant;
+
+
+/// The default smoothing factor used by a [`Pacer`].
+const DEFAULT_SMOOTHING: f64 = 0.2;
+
+
+/// A type for proactively pacing requests in order to stay within a
+/// requests-per-minute budget.
+///
+/// A `Pacer` keeps an exponentially weighted moving average of the
+/// intervals between requests and delays a request whenever issuing it
+/// right away would push this average below the interval implied by
+/// the configured budget. As a result, short bursts of requests are
+/// permitted after periods of inactivity, but over time requests are
+/// spread out evenly.
+///
+/// Note that pacing happens independently of (and before) any handling
+/// of the HTTP status 429 ("Too Many Requests"), which is still
+/// reported as `RateLimitExceeded` by the individual endpoints.
+#[derive(Clone, Copy, Debug)]
+pub struct Pacer {
+  /// The target interval between two requests, in seconds.
+  interval: f64,
+  /// The weight of the most recent interval in the moving average.
+  smoothing: f64,
+  /// The moving average of the intervals between requests, in
+  /// seconds.
+  average: f64,
+  /// The time the last request was issued at.
+  last: Option<Instant>,
+}
+
+impl Pacer {
+  /// Create a new `Pacer` targeting the given number of requests per
+  /// minute.
+  pub fn new(requests_per_minute: NonZeroU32) -> Self {
+    let interval = 60.0 / f64::from(requests_per_minute.get());
+
+    Self {
+      interval,
+      smoothing: DEFAULT_SMOOTHING,
+      average: interval,
+      last: None,
+    }
+  }
+
+  /// Set the smoothing factor to use, i.e., the weight of the most
+  /// recent interval between two requests in the moving average.
+  ///
+  /// The factor is in the range `(0, 1]`. Smaller values allow for
+  /// larger bursts of requests, while a value of `1` enforces a fixed
+  /// interval between any two requests. It defaults to `0.2`.
+  ///
+  /// # Panics
+  /// This method panics if `smoothing` is outside of the supported
+  /// range.
+  pub fn with_smoothing(mut self, smoothing: f64) -> Self {
+    assert!(
+      smoothing > 0.0 && smoothing <= 1.0,
+      "invalid smoothing factor: {}",
+      smoothing
+    );
+    self.smoothing = smoothing;
+    self
+  }
+
+  /// Calculate the time elapsed since the last request, capped to a
+  /// value that bounds the burst permitted after idle periods.
+  fn elapsed(&self, last: Instant, now: Instant) -> f64 {
+    let elapsed = now.saturating_duration_since(last).as_secs_f64();
+    elapsed.min(self.interval / self.smoothing)
+  }
+
+  /// Calculate the delay to apply before issuing a request at `now`.
+  fn delay(&self, now: Instant) -> Duration {
+    let last = match self.last {
+      Some(last) => last,
+      None => return Duration::ZERO,
+    };
+
+    self.delay_after(self.elapsed(last, now))
+  }
+
+  /// Calculate the delay to apply before issuing a request `elapsed`
+  /// seconds after the last one.
+  fn delay_after(&self, elapsed: f64) -> Duration {
+    let average = self.smoothing * elapsed + (1.0 - self.smoothing) * self.average;
+    if average >= self.interval {
+      Duration::ZERO
+    } else {
+      // The elapsed time at which the moving average would reach the
+      // target interval. Due to rounding this value may end up ever so
+      // slightly below `elapsed` when the average is close to the
+      // interval.
+      let required = (self.interval - (1.0 - self.smoothing) * self.average) / self.smoothing;
+      Duration::from_secs_f64((required - elapsed).max(0.0))
+    }
+  }
+
+  /// Record a request as being issued at `now`.
+  fn record(&mut self, now: Instant) {
+    if let Some(last) = self.last {
+      let elapsed = self.elapsed(last, now);
+      self.average = self.smoothing * elapsed + (1.0 - self.smoothing) * self.average;
+    }
+    self.last = Some(now);
+  }
+
+  /// Wait until the next request can be issued within the budget and
+  /// account for it.
+  pub async fn pace(&mut self) {
+    let delay = self.delay(Instant::now());
+    if !delay.is_zero() {
+      sleep(delay).await;
+    }
+    self.record(Instant::now())
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+
+  /// Create a `Pacer` for the given budget.
+  fn pacer(requests_per_minute: u32) -> Pacer {
+    Pacer::new(NonZeroU32::new(requests_per_minute).unwrap())
+  }
+
+
+  /// Check that a fixed interval is enforced with a smoothing factor
+  /// of one.
+  #[test]
+  fn fixed_interval() {
+    let mut pacer = pacer(60).with_smoothing(1.0);
+    let start = Instant::now();
+
+    assert_eq!(pacer.delay(start), Duration::ZERO);
+    pacer.record(start);
+
+    let now = start + Duration::from_millis(250);
+    assert_eq!(pacer.delay(now), Duration::from_millis(750));
+
+    let now = start + Duration::from_secs(1);
+    assert_eq!(pacer.delay(now), Duration::ZERO);
+  }
+
+  /// Make sure that a burst of requests gets smoothed out.
+  #[test]
+  fn burst_smoothing() {
+    let mut pacer = pacer(120);
+    let mut now = Instant::now();
+    let mut delayed = 0;
+
+    // Issue requests as quickly as possible and check that, after an
+    // initial burst, requests are delayed.
+    for _ in 0..10 {
+      let delay = pacer.delay(now);
+      if !delay.is_zero() {
+        delayed += 1;
+      }
+      now += delay;
+      pacer.record(now);
+    }
+    assert!(delayed > 0);
+
+    // Once we are in steady state, the delays should converge towards
+    // the target interval of half a second.
+    for _ in 0..50 {
+      let delay = pacer.delay(now);
+      now += delay;
+      pacer.record(now);
+    }
+    let delay = pacer.delay(now).as_secs_f64();
+    assert!((delay - 0.5).abs() < 0.01, "{}", delay);
+  }
+
+  /// Check that the burst permitted after an idle period is bounded.
+  #[test]
+  fn bounded_burst() {
+    let mut pacer = pacer(60);
+    let start = Instant::now();
+    pacer.record(start);
+
+    let now = start + Duration::from_secs(3600);
+    let mut burst = 0;
+    while pacer.delay(now).is_zero() {
+      pacer.record(now);
+      burst += 1;
+      assert!(burst < 10);
+    }
+    assert!(burst > 1);
+  }
+
+  /// Check that rounding errors do not cause a negative delay to be
+  /// calculated.
+  #[test]
+  fn no_negative_delay() {
+    let pacer = Pacer {
+      interval: 0.9585666890899683,
+      smoothing: 0.7,
+      average: 0.9900182190308712,
+      last: None,
+    };
+    assert_eq!(pacer.delay_after(0.9450874619724385), Duration::ZERO);
+  }
+
+  /// Check that we reject invalid smoothing factors.
+  #[test]
+  #[should_panic(expected = "invalid smoothing factor")]
+  fn invalid_smoothing() {
+    let _pacer = pacer(60).with_smoothing(0.0);
+  }
+}
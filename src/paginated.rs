@@ -0,0 +1,17 @@
+// Copyright (C) 2022 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use http_endpoint::Endpoint;
+
+
+/// A trait representing an endpoint that reports its results in the
+/// form of multiple pages.
+pub trait Paginated: Endpoint {
+  /// The type of the items contained in a page.
+  type Item;
+
+  /// Split a page of results into the contained items and the input
+  /// for requesting the next page, if there is one.
+  fn into_page(input: &Self::Input, output: Self::Output)
+    -> (Vec<Self::Item>, Option<Self::Input>);
+}
@@ -1,8 +1,19 @@
 // Copyright (C) 2020-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+// Depending on the features enabled, not all of the helpers may be in
+// use.
+#![cfg_attr(not(all(feature = "data", feature = "trading")), allow(dead_code))]
+
 use crate::Str;
 
+use chrono::DateTime;
+use chrono::Datelike as _;
+use chrono::Duration;
+use chrono::FixedOffset;
+use chrono::NaiveDate;
+use chrono::Utc;
+
 use num_decimal::Num;
 
 use serde::Deserialize;
@@ -12,13 +23,57 @@ use serde::Serializer;
 use serde_variant::to_variant_name;
 
 
-/// Deserialize a `Num` from a string, parsing the value as signed first
-/// and then dropping the sign.
-pub(crate) fn abs_num_from_str<'de, D>(deserializer: D) -> Result<Num, D::Error>
+/// Retrieve the absolute value of a `Num`.
+pub(crate) fn abs(num: Num) -> Num {
+  if num.is_negative() {
+    -num
+  } else {
+    num
+  }
+}
+
+
+/// Retrieve the `n`th (one-based) Sunday of the given month.
+fn nth_sunday(year: i32, month: u32, n: u32) -> NaiveDate {
+  let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+  let days = (7 - first.weekday().num_days_from_sunday()) % 7 + 7 * (n - 1);
+  first + Duration::days(i64::from(days))
+}
+
+
+/// Check whether daylight saving time is in effect in US Eastern time
+/// at the given time.
+///
+/// Daylight saving time starts at 2:00 local time on the second Sunday
+/// in March and ends at 2:00 local time on the first Sunday in November
+/// (the rules in effect since 2007).
+pub(crate) fn is_dst(time: &DateTime<Utc>) -> bool {
+  let year = time.year();
+  // 2:00 EST is 7:00 UTC and 2:00 EDT is 6:00 UTC.
+  let start = nth_sunday(year, 3, 2).and_hms_opt(7, 0, 0).unwrap();
+  let end = nth_sunday(year, 11, 1).and_hms_opt(6, 0, 0).unwrap();
+  let time = time.naive_utc();
+  start <= time && time < end
+}
+
+
+/// Convert a UTC time into US Eastern time, which is what the market
+/// calendar is expressed in.
+pub(crate) fn to_eastern(time: &DateTime<Utc>) -> DateTime<FixedOffset> {
+  let hours = if is_dst(time) { 4 } else { 5 };
+  time.with_timezone(&FixedOffset::west_opt(hours * 3600).unwrap())
+}
+
+
+/// Deserialize a `Num` (or a type constructible from it) from a
+/// string, parsing the value as signed first and then dropping the
+/// sign.
+pub(crate) fn abs_num_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
   D: Deserializer<'de>,
+  T: From<Num>,
 {
-  Num::deserialize(deserializer).map(|num| if num.is_negative() { num * -1 } else { num })
+  Num::deserialize(deserializer).map(abs).map(T::from)
 }
 
 
@@ -94,11 +94,10 @@ pub(crate) mod test {
 
 
   /// Instantiate a dummy websocket server serving messages as per the
-  /// provided function `f` and attempt to connect to it to stream
-  /// messages.
-  pub(crate) async fn mock_stream<S, F, R>(f: F) -> Result<(S::Stream, S::Subscription), Error>
+  /// provided function `f` and create an `ApiInfo` object pointing to
+  /// it.
+  pub(crate) async fn mock_api_info<F, R>(f: F) -> ApiInfo
   where
-    S: Subscribable<Input = ApiInfo>,
     F: FnOnce(WebSocketStream) -> R + Send + Sync + 'static,
     R: Future<Output = Result<(), WebSocketError>> + Send + Sync + 'static,
   {
@@ -108,15 +107,26 @@ pub(crate) mod test {
     // We just set both the API stream URL and the data stream URL to
     // our websocket server. We don't know which one clients are trying
     // to mock, but currently it's only one or the other.
-    let api_info = ApiInfo {
+    ApiInfo {
       api_base_url: Url::parse("http://example.com").unwrap(),
       api_stream_url: stream_url.clone(),
       data_base_url: Url::parse("http://example.com").unwrap(),
-      data_stream_base_url: stream_url.clone(),
+      data_stream_base_url: stream_url,
       key_id: KEY_ID.to_string(),
       secret: SECRET.to_string(),
-    };
+    }
+  }
 
+  /// Instantiate a dummy websocket server serving messages as per the
+  /// provided function `f` and attempt to connect to it to stream
+  /// messages.
+  pub(crate) async fn mock_stream<S, F, R>(f: F) -> Result<(S::Stream, S::Subscription), Error>
+  where
+    S: Subscribable<Input = ApiInfo>,
+    F: FnOnce(WebSocketStream) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<(), WebSocketError>> + Send + Sync + 'static,
+  {
+    let api_info = mock_api_info(f).await;
     S::connect(&api_info).await
   }
 }